@@ -2,9 +2,145 @@
 
 use ink_lang as ink;
 
+use ink_env::{AccountId, DefaultEnvironment, Environment};
+use ink_prelude::{string::String, vec::Vec};
+
+type Balance = <DefaultEnvironment as Environment>::Balance;
+
+/// The PSP22 error type.
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22Error {
+    /// Custom error type for cases not covered by the standard.
+    Custom(String),
+    /// Returned if the balance cannot fulfill a request.
+    InsufficientBalance,
+    /// Returned if the allowance cannot fulfill a request.
+    InsufficientAllowance,
+    /// Returned if the recipient is the zero address.
+    ZeroRecipientAddress,
+    /// Returned if the sender is the zero address.
+    ZeroSenderAddress,
+    /// Returned if a safe transfer check failed.
+    SafeTransferCheckFailed(String),
+}
+
+/// The PSP22 fungible token standard.
+#[ink::trait_definition]
+pub trait PSP22 {
+    /// Returns the total token supply.
+    #[ink(message)]
+    fn total_supply(&self) -> Balance;
+
+    /// Returns the account balance for the specified `owner`.
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> Balance;
+
+    /// Returns the amount which `spender` is still allowed to withdraw from `owner`.
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance;
+
+    /// Transfers `value` tokens from the caller to `to`.
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    /// Transfers `value` tokens on the behalf of `from` to `to`.
+    #[ink(message)]
+    fn transfer_from(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        value: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error>;
+
+    /// Allows `spender` to withdraw from the caller's account up to `value` tokens.
+    #[ink(message)]
+    fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error>;
+
+    /// Atomically increases the allowance granted to `spender` by the caller.
+    #[ink(message)]
+    fn increase_allowance(
+        &mut self,
+        spender: AccountId,
+        delta_value: Balance,
+    ) -> Result<(), PSP22Error>;
+
+    /// Atomically decreases the allowance granted to `spender` by the caller.
+    #[ink(message)]
+    fn decrease_allowance(
+        &mut self,
+        spender: AccountId,
+        delta_value: Balance,
+    ) -> Result<(), PSP22Error>;
+}
+
+/// The PSP22 receiver error type.
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22ReceiverError {
+    /// Returned if the receiver does not accept the transfer.
+    TransferRejected(String),
+}
+
+/// Hook implemented by contracts that want to accept PSP22 tokens.
+#[ink::trait_definition]
+pub trait PSP22Receiver {
+    /// Called before `value` tokens are transferred from `from` to this contract
+    /// on behalf of `operator`. Returning an error rejects the transfer.
+    #[ink(message)]
+    fn before_received(
+        &mut self,
+        operator: AccountId,
+        from: AccountId,
+        value: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22ReceiverError>;
+}
+
+/// The flash borrower error type.
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum FlashBorrowerError {
+    /// Returned if the borrower does not accept the flash loan.
+    FlashloanRejected(String),
+}
+
+/// Hook implemented by contracts that take ERC-3156 style flash loans.
+#[ink::trait_definition]
+pub trait FlashBorrower {
+    /// Called after `amount` tokens of `token` were lent to this contract on behalf
+    /// of `initiator`. Before returning, the borrower must approve the lender to take
+    /// back `amount + fee` tokens.
+    #[ink(message)]
+    fn on_flash_loan(
+        &mut self,
+        initiator: AccountId,
+        token: AccountId,
+        amount: Balance,
+        fee: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), FlashBorrowerError>;
+}
+
+/// Reference to any contract implementing [`PSP22`], usable for cross-contract calls.
+pub type PSP22Ref = <<ink_lang::reflect::TraitDefinitionRegistry<DefaultEnvironment> as PSP22>::__ink_TraitInfo as ink_lang::codegen::TraitCallForwarder>::Forwarder;
+
 #[ink::contract]
 mod erc20 {
-    use ink_storage::{traits::SpreadAllocate, Mapping};
+    use super::{FlashBorrowerError, PSP22Error, PSP22ReceiverError, PSP22};
+    use ink_env::hash::Blake2x256;
+    #[cfg(not(test))]
+    use ink_env::{
+        call::{build_call, Call, ExecutionInput, FromAccountId, Selector},
+        CallFlags,
+    };
+    use ink_prelude::{boxed::Box, format, string::String, vec::Vec};
+    use ink_primitives::Key;
+    use ink_storage::{
+        traits::{pull_spread_root, push_spread_root, PackedLayout, SpreadAllocate, SpreadLayout},
+        Mapping,
+    };
 
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -12,11 +148,178 @@ mod erc20 {
         /// Return if the balance cannot fulfill a request.
         InsufficientBalance,
         InsufficientAllowance,
+        /// Returned if the caller is not the contract owner.
+        NotOwner,
+        /// Returned if a balance, allowance or supply computation would overflow.
+        Overflow,
+        /// Returned if a balance, allowance or supply computation would underflow.
+        Underflow,
+        /// Returned if the current allowance does not match the expected one.
+        AllowanceChanged,
+        /// Returned if the recipient contract rejected or failed to handle a transfer.
+        SafeTransferCheckFailed(String),
+        /// Returned if tokens are moved while the contract is paused.
+        Paused,
+        /// Returned if the contract is unpaused while not paused.
+        NotPaused,
+        /// Returned if the caller is missing the role required for an operation.
+        MissingRole,
+        /// Returned if the caller cannot act on behalf of the given account.
+        InvalidCaller,
+        /// Returned if the caller is not the pending owner.
+        NotPendingOwner,
+        /// Returned if there is no pending ownership transfer.
+        NoPendingOwner,
+        /// Returned if a permit is submitted after its deadline.
+        PermitExpired,
+        /// Returned if a permit signature was not produced by the owner.
+        InvalidSignature,
+        /// Returned if a snapshot id has not been taken yet.
+        InvalidSnapshotId,
+        /// Returned if past votes are queried for a block that is not yet final.
+        FutureLookup,
+        /// Returned if an operation would raise the total supply above the cap.
+        CapExceeded,
+        /// Returned if a flash loan is requested for a token other than this one.
+        UnsupportedToken,
+        /// Returned if a flash loan exceeds `max_flash_loan`.
+        ExceededMaxFlashLoan,
+        /// Returned if the borrower rejected or failed to handle a flash loan.
+        FlashLoanRejected(String),
+        /// Returned if the borrower did not allow the loan and fee to be taken back.
+        FlashLoanRepaymentFailed,
+        /// Returned if sending native currency from the contract failed.
+        NativeTransferFailed,
+        /// Returned if the contract does not wrap an underlying token.
+        NotWrapper,
+        /// Returned if the contract does not wrap native currency.
+        NotNativeWrapper,
+        /// Returned if tokens are minted on a wrapper, whose supply must stay fully
+        /// backed.
+        MintingDisabled,
+        /// Returned if tokens are burned on a wrapper, whose supply must stay fully
+        /// backed.
+        BurningDisabled,
+        /// Returned if a vesting schedule has a zero amount or duration, or a cliff
+        /// beyond its duration.
+        InvalidVestingSchedule,
+        /// Returned if a vesting schedule does not exist.
+        VestingScheduleNotFound,
+        /// Returned if a vesting schedule cannot be revoked.
+        VestingNotRevocable,
+        /// Returned if a transfer would move tokens that are still time-locked.
+        TokensLocked,
+        /// Returned if tokens are locked until a timestamp that has already passed.
+        InvalidUnlockTime,
+        /// Returned if a time lock would lock no tokens.
+        ZeroLockValue,
+        /// Returned if an account already has `MAX_LOCKS_PER_ACCOUNT` active time
+        /// locks.
+        TooManyLocks,
+        /// Returned if a transfer fee above `MAX_TRANSFER_FEE_BPS` is configured.
+        FeeTooHigh,
+        /// Returned if a frozen account sends, receives, burns or spends tokens.
+        AccountFrozen,
+        /// Returned if an account outside the allowlist would hold tokens.
+        NotAllowlisted,
+        /// Returned if the entry at the given index of a batch transfer failed.
+        BatchTransferFailed(u32, Box<Error>),
+    }
+
+    /// Identifier of an access control role.
+    pub type RoleType = u32;
+
+    /// Role that administers every other role unless configured otherwise.
+    pub const DEFAULT_ADMIN_ROLE: RoleType = 0;
+    /// Role allowed to mint new tokens.
+    pub const MINTER_ROLE: RoleType = ink_lang::selector_id!("MINTER");
+    /// Role allowed to pause and unpause token transfers.
+    pub const PAUSER_ROLE: RoleType = ink_lang::selector_id!("PAUSER");
+    /// Role allowed to take balance snapshots.
+    pub const SNAPSHOT_ROLE: RoleType = ink_lang::selector_id!("SNAPSHOT");
+    /// Role allowed to move tokens between accounts without holder consent.
+    pub const CONTROLLER_ROLE: RoleType = ink_lang::selector_id!("CONTROLLER");
+    /// Role allowed to send time-locked transfers.
+    pub const LOCKER_ROLE: RoleType = ink_lang::selector_id!("LOCKER");
+
+    /// Highest number of active time locks an account can have.
+    pub const MAX_LOCKS_PER_ACCOUNT: u32 = 8;
+
+    /// Highest transfer fee that can be configured, in basis points.
+    pub const MAX_TRANSFER_FEE_BPS: u16 = 1_000;
+
+    /// Identifier of a balance snapshot, starting at 1.
+    pub type SnapshotId = u32;
+
+    /// Linear release of tokens to a beneficiary.
+    #[derive(
+        Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct VestingSchedule {
+        /// Total amount of tokens locked in the schedule.
+        pub amount: Balance,
+        /// Amount of tokens already released to the beneficiary.
+        pub released: Balance,
+        /// Timestamp the schedule starts vesting at.
+        pub start: Timestamp,
+        /// Time after `start` before which nothing vests.
+        pub cliff: Timestamp,
+        /// Time after `start` at which everything has vested.
+        pub duration: Timestamp,
+        /// Whether an admin can revoke the unvested tokens.
+        pub revocable: bool,
+        /// Whether the schedule has been revoked.
+        pub revoked: bool,
+    }
+
+    impl VestingSchedule {
+        /// Returns the amount vested at timestamp `now`.
+        fn vested_amount(&self, now: Timestamp) -> Balance {
+            let elapsed = now.saturating_sub(self.start);
+            if self.revoked || elapsed >= self.duration {
+                return self.amount;
+            }
+            if elapsed < self.cliff {
+                return 0;
+            }
+            mul_div_floor(
+                self.amount,
+                Balance::from(elapsed),
+                Balance::from(self.duration),
+            )
+        }
+    }
+
+    /// Returns `value * numerator / denominator` rounded down, for `numerator` not
+    /// above `denominator`.
+    ///
+    /// Split as `value / denominator * numerator` plus the remainder's share, so it
+    /// cannot overflow as long as `denominator * denominator` fits in a `Balance`,
+    /// even when `value * numerator` does not.
+    fn mul_div_floor(value: Balance, numerator: Balance, denominator: Balance) -> Balance {
+        value / denominator * numerator + value % denominator * numerator / denominator
     }
 
     /// Specify the ERC-20 result tyle.
     pub type Result<T> = core::result::Result<T, Error>;
 
+    impl From<Error> for PSP22Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InsufficientBalance => PSP22Error::InsufficientBalance,
+                Error::InsufficientAllowance => PSP22Error::InsufficientAllowance,
+                Error::SafeTransferCheckFailed(reason) => {
+                    PSP22Error::SafeTransferCheckFailed(reason)
+                }
+                error => PSP22Error::Custom(format!("{:?}", error)),
+            }
+        }
+    }
+
     #[ink(event)]
     pub struct Transfer {
         #[ink(topic)]
@@ -35,6 +338,215 @@ mod erc20 {
         value: Balance,
     }
 
+    /// Event emitted when token transfers are paused.
+    #[ink(event)]
+    pub struct Paused {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Event emitted when token transfers are resumed.
+    #[ink(event)]
+    pub struct Unpaused {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Event emitted when `role` is granted to `grantee`.
+    #[ink(event)]
+    pub struct RoleGranted {
+        #[ink(topic)]
+        role: RoleType,
+        #[ink(topic)]
+        grantee: AccountId,
+        #[ink(topic)]
+        grantor: AccountId,
+    }
+
+    /// Event emitted when `role` is revoked from `account`.
+    #[ink(event)]
+    pub struct RoleRevoked {
+        #[ink(topic)]
+        role: RoleType,
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        sender: AccountId,
+    }
+
+    /// Event emitted when the admin role of `role` changes.
+    #[ink(event)]
+    pub struct RoleAdminChanged {
+        #[ink(topic)]
+        role: RoleType,
+        #[ink(topic)]
+        previous_admin_role: RoleType,
+        #[ink(topic)]
+        new_admin_role: RoleType,
+    }
+
+    /// Event emitted when the owner starts an ownership transfer.
+    #[ink(event)]
+    pub struct OwnershipTransferStarted {
+        #[ink(topic)]
+        previous_owner: AccountId,
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
+
+    /// Event emitted when the owner cancels a pending ownership transfer.
+    #[ink(event)]
+    pub struct OwnershipTransferCanceled {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        pending_owner: AccountId,
+    }
+
+    /// Event emitted when ownership is accepted or renounced.
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous_owner: Option<AccountId>,
+        #[ink(topic)]
+        new_owner: Option<AccountId>,
+    }
+
+    /// Event emitted when a balance snapshot is taken.
+    #[ink(event)]
+    pub struct Snapshot {
+        id: SnapshotId,
+    }
+
+    /// Event emitted when an account changes its delegate.
+    #[ink(event)]
+    pub struct DelegateChanged {
+        #[ink(topic)]
+        delegator: AccountId,
+        #[ink(topic)]
+        from_delegate: Option<AccountId>,
+        #[ink(topic)]
+        to_delegate: Option<AccountId>,
+    }
+
+    /// Event emitted when the votes of a delegate change.
+    #[ink(event)]
+    pub struct DelegateVotesChanged {
+        #[ink(topic)]
+        delegate: AccountId,
+        previous_votes: Balance,
+        new_votes: Balance,
+    }
+
+    /// Event emitted when native currency is wrapped into tokens.
+    #[ink(event)]
+    pub struct Deposit {
+        #[ink(topic)]
+        account: AccountId,
+        value: Balance,
+    }
+
+    /// Event emitted when tokens are unwrapped into native currency.
+    #[ink(event)]
+    pub struct Withdrawal {
+        #[ink(topic)]
+        account: AccountId,
+        value: Balance,
+    }
+
+    /// Event emitted when tokens are locked in a new vesting schedule.
+    #[ink(event)]
+    pub struct VestingCreated {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        index: u32,
+        amount: Balance,
+    }
+
+    /// Event emitted when vested tokens are released to a beneficiary.
+    #[ink(event)]
+    pub struct VestingReleased {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        amount: Balance,
+    }
+
+    /// Event emitted when a vesting schedule is revoked.
+    #[ink(event)]
+    pub struct VestingRevoked {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        index: u32,
+        refund: Balance,
+    }
+
+    /// Event emitted when tokens are sent locked until `unlock_at`.
+    #[ink(event)]
+    pub struct LockedTransfer {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+        unlock_at: Timestamp,
+    }
+
+    /// Event emitted when the transfer fee configuration changes.
+    #[ink(event)]
+    pub struct TransferFeeChanged {
+        fee_bps: u16,
+        #[ink(topic)]
+        treasury: Option<AccountId>,
+    }
+
+    /// Event emitted when an account is frozen.
+    #[ink(event)]
+    pub struct AccountFrozen {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Event emitted when an account is unfrozen.
+    #[ink(event)]
+    pub struct AccountUnfrozen {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Event emitted when an account is added to the allowlist.
+    #[ink(event)]
+    pub struct Allowlisted {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Event emitted when an account is removed from the allowlist.
+    #[ink(event)]
+    pub struct AllowlistRemoved {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Event emitted when allowlist mode is disabled.
+    #[ink(event)]
+    pub struct AllowlistDisabled {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Event emitted when a controller forcibly moves tokens.
+    #[ink(event)]
+    pub struct ControllerTransfer {
+        #[ink(topic)]
+        controller: AccountId,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+        reason: Vec<u8>,
+    }
+
     /// Create storage for a simple ERC-20 contract.
     #[ink(storage)]
     #[derive(SpreadAllocate)]
@@ -45,6 +557,73 @@ mod erc20 {
         balances: Mapping<AccountId, Balance>,
         /// Balances that can be transferred by non-owners: (owner, spender) -> allowed
         allowances: Mapping<(AccountId, AccountId), Balance>,
+        /// Whether this contract wraps native currency 1:1.
+        native_wrapper: bool,
+        /// Number of vesting schedules per beneficiary.
+        vesting_schedule_counts: Mapping<AccountId, u32>,
+        /// Vesting schedules: (beneficiary, index) -> schedule
+        vesting_schedules: Mapping<(AccountId, u32), VestingSchedule>,
+        /// Number of time locks recorded per account.
+        lock_counts: Mapping<AccountId, u32>,
+        /// Time locks: (account, index) -> (locked value, unlock timestamp)
+        locks: Mapping<(AccountId, u32), (Balance, Timestamp)>,
+        /// Fee charged on transfers, in basis points of the transferred value.
+        transfer_fee_bps: u16,
+        /// Accounts whose transfers are exempt from fees.
+        fee_exempt: Mapping<AccountId, ()>,
+        /// Accounts that can neither send, receive, burn nor spend tokens.
+        frozen: Mapping<AccountId, ()>,
+        /// Whether only allowlisted accounts may hold tokens.
+        allowlist_enabled: bool,
+        /// Accounts allowed to hold tokens in allowlist mode.
+        allowlist: Mapping<AccountId, ()>,
+        /// Number of decimals used to display token amounts.
+        decimals: u8,
+        /// Whether token transfers are currently halted.
+        paused: bool,
+        /// Granted roles: (role, account) -> ()
+        roles: Mapping<(RoleType, AccountId), ()>,
+        /// Admin role of each role, defaults to `DEFAULT_ADMIN_ROLE`.
+        role_admins: Mapping<RoleType, RoleType>,
+        /// Number of permits used by each owner.
+        nonces: Mapping<AccountId, u64>,
+        /// Id of the most recent snapshot, 0 if none was taken.
+        current_snapshot_id: SnapshotId,
+        /// Number of balance snapshots recorded per account.
+        account_snapshot_counts: Mapping<AccountId, u32>,
+        /// Balance snapshots: (account, index) -> (snapshot id, balance)
+        account_snapshots: Mapping<(AccountId, u32), (SnapshotId, Balance)>,
+        /// Number of recorded total supply snapshots.
+        total_supply_snapshot_count: u32,
+        /// Total supply snapshots: index -> (snapshot id, total supply)
+        total_supply_snapshots: Mapping<u32, (SnapshotId, Balance)>,
+        /// Account each holder delegates its votes to.
+        delegates: Mapping<AccountId, AccountId>,
+        /// Number of vote checkpoints recorded per delegate.
+        vote_checkpoint_counts: Mapping<AccountId, u32>,
+        /// Vote checkpoints: (delegate, index) -> (block number, votes)
+        vote_checkpoints: Mapping<(AccountId, u32), (BlockNumber, Balance)>,
+        /// Number of recorded total supply checkpoints.
+        total_supply_checkpoint_count: u32,
+        /// Total supply checkpoints: index -> (block number, total supply)
+        total_supply_checkpoints: Mapping<u32, (BlockNumber, Balance)>,
+        // `Option` fields must come after every `Mapping`: ink! 3 allocates them one
+        // storage cell short of where they are read from, which would shift the keys
+        // of any `Mapping` declared after them.
+        /// Token wrapped 1:1 by this contract, if any.
+        underlying: Option<AccountId>,
+        /// Account receiving transfer fees, no fee is charged if unset.
+        treasury: Option<AccountId>,
+        /// Maximum total supply, if capped.
+        cap: Option<Balance>,
+        /// Optional name of the token.
+        name: Option<String>,
+        /// Optional symbol of the token.
+        symbol: Option<String>,
+        /// Current owner of the contract, `None` once renounced.
+        owner: Option<AccountId>,
+        /// Account that must accept a pending ownership transfer.
+        pending_owner: Option<AccountId>,
     }
 
     impl Erc20 {
@@ -57,17 +636,100 @@ mod erc20 {
             })
         }
 
+        /// Create a new ERC-20 contract with an initial supply and token metadata.
+        #[ink(constructor)]
+        pub fn new_with_metadata(
+            initial_supply: Balance,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+        ) -> Self {
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                contract.name = name;
+                contract.symbol = symbol;
+                contract.decimals = decimals;
+                Self::new_init(contract, initial_supply)
+            })
+        }
+
+        /// Create a new ERC-20 contract with an initial supply whose total supply can
+        /// never exceed `cap`.
+        ///
+        /// # Panics
+        ///
+        /// If `initial_supply` is greater than `cap`.
+        #[ink(constructor)]
+        pub fn new_capped(initial_supply: Balance, cap: Balance) -> Self {
+            assert!(initial_supply <= cap, "initial supply exceeds the cap");
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                contract.cap = Some(cap);
+                Self::new_init(contract, initial_supply)
+            })
+        }
+
+        /// Create a new ERC-20 contract that wraps native currency 1:1.
+        ///
+        /// Tokens only come into existence through `deposit`.
+        #[ink(constructor)]
+        pub fn new_native_wrapper() -> Self {
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                contract.native_wrapper = true;
+                Self::new_init(contract, 0)
+            })
+        }
+
+        /// Create a new ERC-20 contract that wraps the `underlying` token 1:1.
+        ///
+        /// The underlying token must implement the messages of this contract.
+        #[ink(constructor)]
+        pub fn new_wrapper(underlying: AccountId) -> Self {
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                contract.underlying = Some(underlying);
+                Self::new_init(contract, 0)
+            })
+        }
+
+        /// Create a new ERC-20 contract with an initial supply where, if
+        /// `allowlist_enabled` is set, only allowlisted accounts may hold tokens.
+        ///
+        /// Allowlist mode can only be enabled here and, once disabled, stays off.
+        #[ink(constructor)]
+        pub fn new_with_allowlist(initial_supply: Balance, allowlist_enabled: bool) -> Self {
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                contract.allowlist_enabled = allowlist_enabled;
+                Self::new_init(contract, initial_supply)
+            })
+        }
+
         /// Initialize the ERC-20 contract with the specified initial supply.
+        ///
+        /// In allowlist mode the caller is allowlisted so that it keeps its tokens,
+        /// and so is this contract, which holds the tokens of vesting schedules.
         fn new_init(&mut self, initial_supply: Balance) {
             let caller = Self::env().caller();
-            self.balances.insert(&caller, &initial_supply);
+            if self.allowlist_enabled {
+                self.allowlist_insert(caller);
+                self.allowlist_insert(Self::env().account_id());
+            }
+            self.balances.insert(caller, &initial_supply);
             self.total_supply = initial_supply;
+            self.push_total_supply_checkpoint(initial_supply);
+            self.owner = Some(caller);
+            self.grant_role_impl(DEFAULT_ADMIN_ROLE, caller);
+            self.grant_role_impl(MINTER_ROLE, caller);
+            self.grant_role_impl(PAUSER_ROLE, caller);
+            self.grant_role_impl(SNAPSHOT_ROLE, caller);
+            self.grant_role_impl(LOCKER_ROLE, caller);
 
             Self::env().emit_event(Transfer {
                 from: None,
                 to: Some(caller),
                 value: initial_supply,
             });
+            Self::env().emit_event(OwnershipTransferred {
+                previous_owner: None,
+                new_owner: Some(caller),
+            });
         }
 
         /// Returns the total token supply.
@@ -76,115 +738,3845 @@ mod erc20 {
             self.total_supply
         }
 
-        /// Returns the account balance for the specified `owner`.
+        /// Returns the maximum total supply, if capped.
         #[ink(message)]
-        pub fn balance_of(&self, owner: AccountId) -> Balance {
-            self.balances.get(owner).unwrap_or_default()
+        pub fn cap(&self) -> Option<Balance> {
+            self.cap
         }
 
+        /// Returns the token name, if any.
         #[ink(message)]
-        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
-            let from = self.env().caller();
-            self.transfer_from_to(&from, &to, value)
+        pub fn token_name(&self) -> Option<String> {
+            self.name.clone()
         }
 
-        fn transfer_from_to(
-            &mut self,
-            from: &AccountId,
-            to: &AccountId,
-            value: Balance,
-        ) -> Result<()> {
-            let from_balance = self.balance_of_impl(from);
-            if from_balance < value {
-                return Err(Error::InsufficientBalance);
-            }
-
-            self.balances.insert(from, &(from_balance - value));
-            let to_balance = self.balance_of_impl(to);
-            self.balances.insert(to, &(to_balance + value));
-
-            self.env().emit_event(Transfer {
-                from: Some(*from),
-                to: Some(*to),
-                value,
-            });
-
-            Ok(())
+        /// Returns the token symbol, if any.
+        #[ink(message)]
+        pub fn token_symbol(&self) -> Option<String> {
+            self.symbol.clone()
         }
 
-        #[inline]
-        fn balance_of_impl(&self, owner: &AccountId) -> Balance {
-            self.balances.get(owner).unwrap_or_default()
+        /// Returns the number of decimals of the token.
+        #[ink(message)]
+        pub fn token_decimals(&self) -> u8 {
+            self.decimals
         }
 
+        /// Returns the account balance for the specified `owner`.
+        ///
+        /// This includes time-locked tokens, see `spendable_balance_of`.
         #[ink(message)]
-        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()> {
-            let owner = self.env().caller();
-            self.allowances.insert((&owner, &spender), &value);
-            self.env().emit_event(Approval {
-                owner,
-                spender,
-                value,
-            });
-            Ok(())
+        pub fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or_default()
         }
 
+        /// Returns the part of the balance of `owner` that is not time-locked.
         #[ink(message)]
-        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
-            self.allowance_impl(&owner, &spender)
+        pub fn spendable_balance_of(&self, owner: AccountId) -> Balance {
+            self.balance_of_impl(&owner)
+                .saturating_sub(self.locked_balance_of(owner))
         }
 
-        #[inline]
-        fn allowance_impl(&self, owner: &AccountId, spender: &AccountId) -> Balance {
-            self.allowances.get((owner, spender)).unwrap_or_default()
+        /// Returns the amount of tokens of `account` that are still time-locked.
+        #[ink(message)]
+        pub fn locked_balance_of(&self, account: AccountId) -> Balance {
+            let now = self.env().block_timestamp();
+            (0..self.lock_counts.get(account).unwrap_or_default())
+                .filter_map(|index| self.locks.get((account, index)))
+                .filter(|(_, unlock_at)| *unlock_at > now)
+                .map(|(value, _)| value)
+                .fold(0, Balance::saturating_add)
         }
 
-        /// Transfers token on the behalf of the `from` account to the `to` account.
+        /// Transfers `value` tokens from the caller to `to`, which cannot spend them
+        /// before `unlock_at`.
+        ///
+        /// Requires `LOCKER_ROLE`, so that nobody else can fill the lock slots of `to`,
+        /// which can have at most `MAX_LOCKS_PER_ACCOUNT` active locks.
         #[ink(message)]
-        pub fn transfer_from(
+        pub fn transfer_locked(
             &mut self,
-            from: AccountId,
             to: AccountId,
             value: Balance,
+            unlock_at: Timestamp,
         ) -> Result<()> {
-            let caller = self.env().caller();
-            let allowance = self.allowance_impl(&from, &caller);
-            if allowance < value {
-                return Err(Error::InsufficientAllowance);
+            self.ensure_role(LOCKER_ROLE)?;
+            if unlock_at <= self.env().block_timestamp() {
+                return Err(Error::InvalidUnlockTime);
+            }
+            if value == 0 {
+                return Err(Error::ZeroLockValue);
+            }
+            if self.active_lock_count(&to) >= MAX_LOCKS_PER_ACCOUNT {
+                return Err(Error::TooManyLocks);
             }
+            let from = self.env().caller();
+            let fee = self
+                .transfer_fee_of(&from, &to, value)
+                .map_or(0, |(_, fee)| fee);
             self.transfer_from_to(&from, &to, value)?;
-            self.allowances
-                .insert((&from, &caller), &(allowance - value));
+            self.add_lock(&to, value - fee, unlock_at);
+            self.env().emit_event(LockedTransfer {
+                from,
+                to,
+                value,
+                unlock_at,
+            });
             Ok(())
         }
-    }
-
-    #[cfg(test)]
-    mod tests {
-        use super::*;
 
-        use ink_lang as ink;
+        /// Returns the number of non-empty time locks of `account` that have not
+        /// expired.
+        fn active_lock_count(&self, account: &AccountId) -> u32 {
+            let now = self.env().block_timestamp();
+            let count = (0..self.lock_counts.get(account).unwrap_or_default())
+                .filter_map(|index| self.locks.get((account, &index)))
+                .filter(|(value, unlock_at)| *value > 0 && *unlock_at > now)
+                .count();
+            count as u32
+        }
 
-        #[ink::test]
-        fn new_works() {
-            let contract = Erc20::new(777);
-            assert_eq!(contract.total_supply(), 777);
+        /// Records a time lock for `account`, dropping its expired and empty locks.
+        fn add_lock(&mut self, account: &AccountId, value: Balance, unlock_at: Timestamp) {
+            let now = self.env().block_timestamp();
+            let count = self.lock_counts.get(account).unwrap_or_default();
+            let mut active = 0;
+            for index in 0..count {
+                let lock = self.locks.get((account, &index)).unwrap_or_default();
+                if lock.0 > 0 && lock.1 > now {
+                    self.locks.insert((account, &active), &lock);
+                    active += 1;
+                }
+            }
+            for index in active..count {
+                self.locks.remove((account, &index));
+            }
+            self.locks.insert((account, &active), &(value, unlock_at));
+            self.lock_counts.insert(account, &(active + 1));
+        }
+
+        /// Takes `value` tokens out of the active time locks of `account`, oldest
+        /// lock first.
+        fn release_locks(&mut self, account: &AccountId, mut value: Balance) {
+            let now = self.env().block_timestamp();
+            for index in 0..self.lock_counts.get(account).unwrap_or_default() {
+                if value == 0 {
+                    return;
+                }
+                let (locked, unlock_at) = self.locks.get((account, &index)).unwrap_or_default();
+                if unlock_at <= now {
+                    continue;
+                }
+                let released = locked.min(value);
+                value -= released;
+                self.locks
+                    .insert((account, &index), &(locked - released, unlock_at));
+            }
+        }
+
+        /// Returns an error if `account` cannot spend `value` out of `balance`
+        /// because of its time locks.
+        fn ensure_unlocked(
+            &self,
+            account: &AccountId,
+            balance: Balance,
+            value: Balance,
+        ) -> Result<()> {
+            if balance.saturating_sub(self.locked_balance_of(*account)) < value {
+                return Err(Error::TokensLocked);
+            }
+            Ok(())
+        }
+
+        /// Returns the owner of the contract.
+        #[ink(message)]
+        pub fn owner(&self) -> Option<AccountId> {
+            self.owner
+        }
+
+        /// Returns the account that can accept a pending ownership transfer.
+        #[ink(message)]
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner
+        }
+
+        /// Starts transferring ownership to `new_owner`, who must accept it.
+        ///
+        /// Can only be called by the contract owner.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+            let owner = self.ensure_owner()?;
+            self.pending_owner = Some(new_owner);
+            self.env().emit_event(OwnershipTransferStarted {
+                previous_owner: owner,
+                new_owner,
+            });
+            Ok(())
+        }
+
+        /// Accepts a pending ownership transfer, taking over `DEFAULT_ADMIN_ROLE`
+        /// from the previous owner.
+        ///
+        /// Can only be called by the pending owner.
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            if self.pending_owner != Some(caller) {
+                return Err(Error::NotPendingOwner);
+            }
+            self.set_owner(Some(caller));
+            Ok(())
+        }
+
+        /// Cancels a pending ownership transfer.
+        ///
+        /// Can only be called by the contract owner.
+        #[ink(message)]
+        pub fn cancel_ownership_transfer(&mut self) -> Result<()> {
+            let owner = self.ensure_owner()?;
+            let pending_owner = self.pending_owner.take().ok_or(Error::NoPendingOwner)?;
+            self.env().emit_event(OwnershipTransferCanceled {
+                owner,
+                pending_owner,
+            });
+            Ok(())
+        }
+
+        /// Leaves the contract without an owner, revoking `DEFAULT_ADMIN_ROLE` from
+        /// the caller.
+        ///
+        /// Can only be called by the contract owner.
+        #[ink(message)]
+        pub fn renounce_ownership(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.set_owner(None);
+            Ok(())
+        }
+
+        /// Replaces the owner and moves `DEFAULT_ADMIN_ROLE` along with ownership.
+        fn set_owner(&mut self, new_owner: Option<AccountId>) {
+            let previous_owner = self.owner;
+            self.owner = new_owner;
+            self.pending_owner = None;
+            if let Some(previous_owner) = previous_owner {
+                self.revoke_role_impl(DEFAULT_ADMIN_ROLE, previous_owner);
+            }
+            if let Some(new_owner) = new_owner {
+                self.grant_role_impl(DEFAULT_ADMIN_ROLE, new_owner);
+            }
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner,
+            });
+        }
+
+        fn ensure_owner(&self) -> Result<AccountId> {
+            let caller = self.env().caller();
+            if self.owner != Some(caller) {
+                return Err(Error::NotOwner);
+            }
+            Ok(caller)
+        }
+
+        /// Creates `value` new tokens and assigns them to `to`.
+        ///
+        /// Requires `MINTER_ROLE`. Not available on wrappers.
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.ensure_role(MINTER_ROLE)?;
+            if self.is_wrapper() {
+                return Err(Error::MintingDisabled);
+            }
+            self.mint_to(&to, value)
+        }
+
+        /// Returns `true` if token transfers are paused.
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Halts all token transfers.
+        ///
+        /// Requires `PAUSER_ROLE`.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+            self.ensure_role(PAUSER_ROLE)?;
+            self.ensure_not_paused()?;
+            self.paused = true;
+            self.env().emit_event(Paused {
+                account: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        /// Resumes token transfers.
+        ///
+        /// Requires `PAUSER_ROLE`.
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+            self.ensure_role(PAUSER_ROLE)?;
+            if !self.paused {
+                return Err(Error::NotPaused);
+            }
+            self.paused = false;
+            self.env().emit_event(Unpaused {
+                account: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        fn ensure_not_paused(&self) -> Result<()> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            Ok(())
+        }
+
+        /// Returns `true` if `account` has been granted `role`.
+        #[ink(message)]
+        pub fn has_role(&self, role: RoleType, account: AccountId) -> bool {
+            self.roles.contains((&role, &account))
+        }
+
+        /// Returns the role that administers `role`.
+        #[ink(message)]
+        pub fn get_role_admin(&self, role: RoleType) -> RoleType {
+            self.role_admins.get(role).unwrap_or(DEFAULT_ADMIN_ROLE)
+        }
+
+        /// Makes `admin_role` the role that administers `role`.
+        ///
+        /// Requires the current admin role of `role`.
+        #[ink(message)]
+        pub fn set_role_admin(&mut self, role: RoleType, admin_role: RoleType) -> Result<()> {
+            let previous_admin_role = self.get_role_admin(role);
+            self.ensure_role(previous_admin_role)?;
+            self.role_admins.insert(role, &admin_role);
+            self.env().emit_event(RoleAdminChanged {
+                role,
+                previous_admin_role,
+                new_admin_role: admin_role,
+            });
+            Ok(())
+        }
+
+        /// Grants `role` to `account`.
+        ///
+        /// Requires the admin role of `role`.
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: RoleType, account: AccountId) -> Result<()> {
+            self.ensure_role(self.get_role_admin(role))?;
+            self.grant_role_impl(role, account);
+            Ok(())
+        }
+
+        /// Revokes `role` from `account`.
+        ///
+        /// Requires the admin role of `role`.
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: RoleType, account: AccountId) -> Result<()> {
+            self.ensure_role(self.get_role_admin(role))?;
+            self.revoke_role_impl(role, account);
+            Ok(())
+        }
+
+        /// Revokes `role` from the caller, who must be `account`.
+        #[ink(message)]
+        pub fn renounce_role(&mut self, role: RoleType, account: AccountId) -> Result<()> {
+            if self.env().caller() != account {
+                return Err(Error::InvalidCaller);
+            }
+            self.revoke_role_impl(role, account);
+            Ok(())
+        }
+
+        fn grant_role_impl(&mut self, role: RoleType, account: AccountId) {
+            if self.has_role(role, account) {
+                return;
+            }
+            self.roles.insert((&role, &account), &());
+            self.env().emit_event(RoleGranted {
+                role,
+                grantee: account,
+                grantor: self.env().caller(),
+            });
+        }
+
+        fn revoke_role_impl(&mut self, role: RoleType, account: AccountId) {
+            if !self.has_role(role, account) {
+                return;
+            }
+            self.roles.remove((&role, &account));
+            self.env().emit_event(RoleRevoked {
+                role,
+                account,
+                sender: self.env().caller(),
+            });
+        }
+
+        fn ensure_role(&self, role: RoleType) -> Result<()> {
+            if !self.has_role(role, self.env().caller()) {
+                return Err(Error::MissingRole);
+            }
+            Ok(())
+        }
+
+        fn mint_to(&mut self, to: &AccountId, value: Balance) -> Result<()> {
+            self.ensure_not_frozen(to)?;
+            self.ensure_allowlisted(to)?;
+            let total_supply = self
+                .total_supply
+                .checked_add(value)
+                .ok_or(Error::Overflow)?;
+            if matches!(self.cap, Some(cap) if total_supply > cap) {
+                return Err(Error::CapExceeded);
+            }
+            let to_balance = self
+                .balance_of_impl(to)
+                .checked_add(value)
+                .ok_or(Error::Overflow)?;
+            self.update_account_snapshot(to);
+            self.update_total_supply_snapshot();
+            self.balances.insert(to, &to_balance);
+            self.total_supply = total_supply;
+            self.push_total_supply_checkpoint(total_supply);
+            self.move_delegate_votes(None, self.delegates.get(to), value)?;
+
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(*to),
+                value,
+            });
+
+            Ok(())
+        }
+
+        /// Destroys `value` tokens from the caller's balance.
+        ///
+        /// Not available on wrappers; use `withdraw` instead.
+        #[ink(message)]
+        pub fn burn(&mut self, value: Balance) -> Result<()> {
+            if self.is_wrapper() {
+                return Err(Error::BurningDisabled);
+            }
+            let caller = self.env().caller();
+            self.burn_from_account(&caller, value)
+        }
+
+        /// Destroys `value` tokens from `account`, deducting from the caller's allowance.
+        ///
+        /// Not available on wrappers.
+        #[ink(message)]
+        pub fn burn_from(&mut self, account: AccountId, value: Balance) -> Result<()> {
+            if self.is_wrapper() {
+                return Err(Error::BurningDisabled);
+            }
+            let caller = self.env().caller();
+            self.ensure_not_frozen(&caller)?;
+            let allowance = self.allowance_impl(&account, &caller);
+            if allowance < value {
+                return Err(Error::InsufficientAllowance);
+            }
+            let allowance = allowance.checked_sub(value).ok_or(Error::Underflow)?;
+            self.burn_from_account(&account, value)?;
+            self.allowances.insert((&account, &caller), &allowance);
+            Ok(())
+        }
+
+        fn burn_from_account(&mut self, account: &AccountId, value: Balance) -> Result<()> {
+            self.ensure_not_frozen(account)?;
+            self.ensure_allowlisted(account)?;
+            let account_balance = self.balance_of_impl(account);
+            if account_balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            self.ensure_unlocked(account, account_balance, value)?;
+
+            let account_balance = account_balance.checked_sub(value).ok_or(Error::Underflow)?;
+            let total_supply = self
+                .total_supply
+                .checked_sub(value)
+                .ok_or(Error::Underflow)?;
+            self.update_account_snapshot(account);
+            self.update_total_supply_snapshot();
+            self.balances.insert(account, &account_balance);
+            self.total_supply = total_supply;
+            self.push_total_supply_checkpoint(total_supply);
+            self.move_delegate_votes(self.delegates.get(account), None, value)?;
+
+            self.env().emit_event(Transfer {
+                from: Some(*account),
+                to: None,
+                value,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            self.transfer_from_to(&from, &to, value)
+        }
+
+        fn transfer_from_to(
+            &mut self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.ensure_not_frozen(from)?;
+            let fee = self.check_recipient(from, to, value)?;
+            let from_balance = self.balance_of_impl(from);
+            if from_balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            self.ensure_unlocked(from, from_balance, value)?;
+
+            match fee {
+                Some((treasury, fee)) => {
+                    self.move_balance(from, to, value - fee)?;
+                    self.move_balance(from, &treasury, fee)
+                }
+                None => self.move_balance(from, to, value),
+            }
+        }
+
+        /// Returns an error if `to` or the treasury cannot receive their share of a
+        /// transfer of `value` tokens from `from`, otherwise the treasury and fee
+        /// charged, if any.
+        fn check_recipient(
+            &self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+        ) -> Result<Option<(AccountId, Balance)>> {
+            self.ensure_not_frozen(to)?;
+            self.ensure_allowlisted(to)?;
+            let fee = self.transfer_fee_of(from, to, value);
+            if let Some((treasury, _)) = fee {
+                self.ensure_not_frozen(&treasury)?;
+                self.ensure_allowlisted(&treasury)?;
+            }
+            Ok(fee)
+        }
+
+        /// Removes `value` tokens from the balance of `account` ahead of crediting
+        /// them to recipients, without checking any transfer restriction.
+        fn debit(&mut self, account: &AccountId, value: Balance) -> Result<()> {
+            let balance = self
+                .balance_of_impl(account)
+                .checked_sub(value)
+                .ok_or(Error::Underflow)?;
+            self.update_account_snapshot(account);
+            self.balances.insert(account, &balance);
+            self.move_delegate_votes(self.delegates.get(account), None, value)
+        }
+
+        /// Adds `value` tokens debited from `from` to the balance of `to`.
+        fn credit(&mut self, from: &AccountId, to: &AccountId, value: Balance) -> Result<()> {
+            let to_balance = self
+                .balance_of_impl(to)
+                .checked_add(value)
+                .ok_or(Error::Overflow)?;
+            self.update_account_snapshot(to);
+            self.balances.insert(to, &to_balance);
+            self.move_delegate_votes(None, self.delegates.get(to), value)?;
+
+            self.env().emit_event(Transfer {
+                from: Some(*from),
+                to: Some(*to),
+                value,
+            });
+
+            Ok(())
+        }
+
+        /// Moves `value` tokens from `from` to `to` without checking any transfer
+        /// restriction.
+        fn move_balance(&mut self, from: &AccountId, to: &AccountId, value: Balance) -> Result<()> {
+            let from_balance = self
+                .balance_of_impl(from)
+                .checked_sub(value)
+                .ok_or(Error::Underflow)?;
+            let to_balance = if from == to {
+                from_balance
+            } else {
+                self.balance_of_impl(to)
+            };
+            let to_balance = to_balance.checked_add(value).ok_or(Error::Overflow)?;
+            self.update_account_snapshot(from);
+            self.update_account_snapshot(to);
+            self.balances.insert(from, &from_balance);
+            self.balances.insert(to, &to_balance);
+            self.move_delegate_votes(self.delegates.get(from), self.delegates.get(to), value)?;
+
+            self.env().emit_event(Transfer {
+                from: Some(*from),
+                to: Some(*to),
+                value,
+            });
+
+            Ok(())
+        }
+
+        /// Returns `true` if `account` is frozen.
+        #[ink(message)]
+        pub fn is_frozen(&self, account: AccountId) -> bool {
+            self.frozen.contains(account)
+        }
+
+        /// Prevents `account` from sending, receiving, burning or spending tokens.
+        ///
+        /// Requires `DEFAULT_ADMIN_ROLE`.
+        #[ink(message)]
+        pub fn freeze(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_role(DEFAULT_ADMIN_ROLE)?;
+            self.frozen.insert(account, &());
+            self.env().emit_event(AccountFrozen { account });
+            Ok(())
+        }
+
+        /// Lifts the freeze of `account`.
+        ///
+        /// Requires `DEFAULT_ADMIN_ROLE`.
+        #[ink(message)]
+        pub fn unfreeze(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_role(DEFAULT_ADMIN_ROLE)?;
+            self.frozen.remove(account);
+            self.env().emit_event(AccountUnfrozen { account });
+            Ok(())
+        }
+
+        /// Returns `true` if only allowlisted accounts may hold tokens.
+        #[ink(message)]
+        pub fn allowlist_enabled(&self) -> bool {
+            self.allowlist_enabled
+        }
+
+        /// Returns `true` if `account` is on the allowlist.
+        #[ink(message)]
+        pub fn is_allowlisted(&self, account: AccountId) -> bool {
+            self.allowlist.contains(account)
+        }
+
+        /// Lets any account hold tokens. Allowlist mode cannot be enabled again, so
+        /// holders who were never allowlisted cannot have their tokens stranded.
+        ///
+        /// Requires `DEFAULT_ADMIN_ROLE`.
+        #[ink(message)]
+        pub fn disable_allowlist(&mut self) -> Result<()> {
+            self.ensure_role(DEFAULT_ADMIN_ROLE)?;
+            if self.allowlist_enabled {
+                self.allowlist_enabled = false;
+                self.env().emit_event(AllowlistDisabled {
+                    account: self.env().caller(),
+                });
+            }
+            Ok(())
+        }
+
+        /// Allows `account` to hold tokens in allowlist mode.
+        ///
+        /// Requires `DEFAULT_ADMIN_ROLE`.
+        #[ink(message)]
+        pub fn allowlist_add(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_role(DEFAULT_ADMIN_ROLE)?;
+            self.allowlist_insert(account);
+            Ok(())
+        }
+
+        /// Removes `account` from the allowlist.
+        ///
+        /// Requires `DEFAULT_ADMIN_ROLE`.
+        #[ink(message)]
+        pub fn allowlist_remove(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_role(DEFAULT_ADMIN_ROLE)?;
+            self.allowlist.remove(account);
+            self.env().emit_event(AllowlistRemoved { account });
+            Ok(())
+        }
+
+        fn allowlist_insert(&mut self, account: AccountId) {
+            self.allowlist.insert(account, &());
+            self.env().emit_event(Allowlisted { account });
+        }
+
+        fn ensure_allowlisted(&self, account: &AccountId) -> Result<()> {
+            if self.allowlist_enabled && !self.allowlist.contains(account) {
+                return Err(Error::NotAllowlisted);
+            }
+            Ok(())
+        }
+
+        fn ensure_not_frozen(&self, account: &AccountId) -> Result<()> {
+            if self.frozen.contains(account) {
+                return Err(Error::AccountFrozen);
+            }
+            Ok(())
+        }
+
+        /// Moves `value` tokens from `from` to `to` without the holder's consent,
+        /// recording `reason` in a `ControllerTransfer` event.
+        ///
+        /// No allowance is needed and pauses, time locks and transfer fees do not
+        /// apply, nor does freezing `from`. But `from` must hold `value` tokens, and
+        /// `to` must not be frozen and must be allowlisted in allowlist mode.
+        ///
+        /// Unlocked tokens of `from` are moved first. Any locked tokens moved are
+        /// taken out of its time locks, oldest lock first.
+        ///
+        /// Requires `CONTROLLER_ROLE`.
+        #[ink(message)]
+        pub fn controller_transfer(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            reason: Vec<u8>,
+        ) -> Result<()> {
+            self.ensure_role(CONTROLLER_ROLE)?;
+            self.ensure_not_frozen(&to)?;
+            self.ensure_allowlisted(&to)?;
+            if self.balance_of_impl(&from) < value {
+                return Err(Error::InsufficientBalance);
+            }
+            let locked = value.saturating_sub(self.spendable_balance_of(from));
+            self.release_locks(&from, locked);
+            self.move_balance(&from, &to, value)?;
+            self.env().emit_event(ControllerTransfer {
+                controller: self.env().caller(),
+                from,
+                to,
+                value,
+                reason,
+            });
+            Ok(())
+        }
+
+        /// Returns the transfer fee in basis points of the transferred value.
+        #[ink(message)]
+        pub fn transfer_fee_bps(&self) -> u16 {
+            self.transfer_fee_bps
+        }
+
+        /// Returns the account receiving transfer fees, if any.
+        #[ink(message)]
+        pub fn treasury(&self) -> Option<AccountId> {
+            self.treasury
+        }
+
+        /// Returns `true` if transfers from or to `account` are exempt from fees.
+        #[ink(message)]
+        pub fn is_fee_exempt(&self, account: AccountId) -> bool {
+            self.fee_exempt.contains(account)
+        }
+
+        /// Charges `fee_bps` basis points of every transfer to `treasury`.
+        ///
+        /// Requires `DEFAULT_ADMIN_ROLE`.
+        #[ink(message)]
+        pub fn set_transfer_fee(
+            &mut self,
+            fee_bps: u16,
+            treasury: Option<AccountId>,
+        ) -> Result<()> {
+            self.ensure_role(DEFAULT_ADMIN_ROLE)?;
+            if fee_bps > MAX_TRANSFER_FEE_BPS {
+                return Err(Error::FeeTooHigh);
+            }
+            self.transfer_fee_bps = fee_bps;
+            self.treasury = treasury;
+            self.env()
+                .emit_event(TransferFeeChanged { fee_bps, treasury });
+            Ok(())
+        }
+
+        /// Sets whether transfers from or to `account` are exempt from fees.
+        ///
+        /// Requires `DEFAULT_ADMIN_ROLE`.
+        #[ink(message)]
+        pub fn set_fee_exempt(&mut self, account: AccountId, exempt: bool) -> Result<()> {
+            self.ensure_role(DEFAULT_ADMIN_ROLE)?;
+            if exempt {
+                self.fee_exempt.insert(account, &());
+            } else {
+                self.fee_exempt.remove(account);
+            }
+            Ok(())
+        }
+
+        /// Returns the treasury and the fee, rounded down, charged on a transfer of
+        /// `value` tokens from `from` to `to`, if any.
+        ///
+        /// Transfers involving the treasury, an exempt account or this contract,
+        /// which holds vesting tokens, are free.
+        fn transfer_fee_of(
+            &self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+        ) -> Option<(AccountId, Balance)> {
+            let treasury = self.treasury?;
+            let contract = self.env().account_id();
+            if [from, to].into_iter().any(|account| {
+                *account == treasury || *account == contract || self.fee_exempt.contains(account)
+            }) {
+                return None;
+            }
+            let fee = mul_div_floor(value, Balance::from(self.transfer_fee_bps), 10_000);
+            (fee > 0).then_some((treasury, fee))
+        }
+
+        #[inline]
+        fn balance_of_impl(&self, owner: &AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or_default()
+        }
+
+        /// Takes a snapshot of all balances and the total supply, returning its id.
+        ///
+        /// Requires `SNAPSHOT_ROLE`.
+        #[ink(message)]
+        pub fn snapshot(&mut self) -> Result<SnapshotId> {
+            self.ensure_role(SNAPSHOT_ROLE)?;
+            let id = self
+                .current_snapshot_id
+                .checked_add(1)
+                .ok_or(Error::Overflow)?;
+            self.current_snapshot_id = id;
+            self.env().emit_event(Snapshot { id });
+            Ok(id)
+        }
+
+        /// Returns the balance of `account` at the time snapshot `id` was taken.
+        #[ink(message)]
+        pub fn balance_of_at(&self, account: AccountId, id: SnapshotId) -> Result<Balance> {
+            self.ensure_snapshot_id(id)?;
+            let count = self
+                .account_snapshot_counts
+                .get(account)
+                .unwrap_or_default();
+            let value = Self::find_snapshot(count, id, |index| {
+                self.account_snapshots
+                    .get((account, index))
+                    .unwrap_or_default()
+            });
+            Ok(value.unwrap_or_else(|| self.balance_of_impl(&account)))
+        }
+
+        /// Returns the total supply at the time snapshot `id` was taken.
+        #[ink(message)]
+        pub fn total_supply_at(&self, id: SnapshotId) -> Result<Balance> {
+            self.ensure_snapshot_id(id)?;
+            let value = Self::find_snapshot(self.total_supply_snapshot_count, id, |index| {
+                self.total_supply_snapshots.get(index).unwrap_or_default()
+            });
+            Ok(value.unwrap_or(self.total_supply))
+        }
+
+        fn ensure_snapshot_id(&self, id: SnapshotId) -> Result<()> {
+            if id == 0 || id > self.current_snapshot_id {
+                return Err(Error::InvalidSnapshotId);
+            }
+            Ok(())
+        }
+
+        /// Returns the value recorded for snapshot `id` in a history of `count`
+        /// entries ordered by snapshot id.
+        ///
+        /// Values are recorded lazily before their first change after a snapshot, so
+        /// the first entry with an id at or after `id` holds the value at `id`. If there
+        /// is none, the value has not changed since and the current value applies.
+        fn find_snapshot<F>(count: u32, id: SnapshotId, entry: F) -> Option<Balance>
+        where
+            F: Fn(u32) -> (SnapshotId, Balance),
+        {
+            let (mut low, mut high) = (0, count);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if entry(mid).0 < id {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            (low < count).then(|| entry(low).1)
+        }
+
+        /// Records the balance of `account` before its first change after the
+        /// current snapshot.
+        fn update_account_snapshot(&mut self, account: &AccountId) {
+            let id = self.current_snapshot_id;
+            if id == 0 {
+                return;
+            }
+            let count = self
+                .account_snapshot_counts
+                .get(account)
+                .unwrap_or_default();
+            if count > 0 {
+                let (last_id, _) = self
+                    .account_snapshots
+                    .get((account, &(count - 1)))
+                    .unwrap_or_default();
+                if last_id == id {
+                    return;
+                }
+            }
+            let balance = self.balance_of_impl(account);
+            self.account_snapshots
+                .insert((account, &count), &(id, balance));
+            self.account_snapshot_counts.insert(account, &(count + 1));
+        }
+
+        /// Delegates the caller's votes to `delegatee`.
+        #[ink(message)]
+        pub fn delegate(&mut self, delegatee: AccountId) -> Result<()> {
+            let delegator = self.env().caller();
+            let from_delegate = self.delegates.get(delegator);
+            self.delegates.insert(delegator, &delegatee);
+            self.env().emit_event(DelegateChanged {
+                delegator,
+                from_delegate,
+                to_delegate: Some(delegatee),
+            });
+            let balance = self.balance_of_impl(&delegator);
+            self.move_delegate_votes(from_delegate, Some(delegatee), balance)
+        }
+
+        /// Returns the account `account` delegates its votes to, if any.
+        #[ink(message)]
+        pub fn delegates(&self, account: AccountId) -> Option<AccountId> {
+            self.delegates.get(account)
+        }
+
+        /// Returns the current votes of `account`.
+        #[ink(message)]
+        pub fn get_votes(&self, account: AccountId) -> Balance {
+            let count = self.vote_checkpoint_counts.get(account).unwrap_or_default();
+            if count == 0 {
+                return 0;
+            }
+            let (_, votes) = self
+                .vote_checkpoints
+                .get((account, count - 1))
+                .unwrap_or_default();
+            votes
+        }
+
+        /// Returns the votes of `account` at the end of block `block`.
+        ///
+        /// `block` must be lower than the current block number.
+        #[ink(message)]
+        pub fn get_past_votes(&self, account: AccountId, block: BlockNumber) -> Result<Balance> {
+            self.ensure_past_block(block)?;
+            let count = self.vote_checkpoint_counts.get(account).unwrap_or_default();
+            Ok(Self::find_checkpoint(count, block, |index| {
+                self.vote_checkpoints
+                    .get((account, index))
+                    .unwrap_or_default()
+            }))
+        }
+
+        /// Returns the total supply at the end of block `block`.
+        ///
+        /// `block` must be lower than the current block number.
+        #[ink(message)]
+        pub fn get_past_total_supply(&self, block: BlockNumber) -> Result<Balance> {
+            self.ensure_past_block(block)?;
+            Ok(Self::find_checkpoint(
+                self.total_supply_checkpoint_count,
+                block,
+                |index| self.total_supply_checkpoints.get(index).unwrap_or_default(),
+            ))
+        }
+
+        fn ensure_past_block(&self, block: BlockNumber) -> Result<()> {
+            if block >= self.env().block_number() {
+                return Err(Error::FutureLookup);
+            }
+            Ok(())
+        }
+
+        /// Returns the value of the last of `count` checkpoints ordered by block
+        /// number that was written at or before `block`, or 0 if there is none.
+        fn find_checkpoint<F>(count: u32, block: BlockNumber, entry: F) -> Balance
+        where
+            F: Fn(u32) -> (BlockNumber, Balance),
+        {
+            let (mut low, mut high) = (0, count);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if entry(mid).0 > block {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+            if low == 0 {
+                return 0;
+            }
+            entry(low - 1).1
+        }
+
+        /// Moves `value` votes from the `from` delegate to the `to` delegate.
+        fn move_delegate_votes(
+            &mut self,
+            from: Option<AccountId>,
+            to: Option<AccountId>,
+            value: Balance,
+        ) -> Result<()> {
+            if from == to || value == 0 {
+                return Ok(());
+            }
+            if let Some(from) = from {
+                let previous_votes = self.get_votes(from);
+                let new_votes = previous_votes.checked_sub(value).ok_or(Error::Underflow)?;
+                self.push_vote_checkpoint(&from, previous_votes, new_votes);
+            }
+            if let Some(to) = to {
+                let previous_votes = self.get_votes(to);
+                let new_votes = previous_votes.checked_add(value).ok_or(Error::Overflow)?;
+                self.push_vote_checkpoint(&to, previous_votes, new_votes);
+            }
+            Ok(())
+        }
+
+        fn push_vote_checkpoint(
+            &mut self,
+            delegate: &AccountId,
+            previous_votes: Balance,
+            new_votes: Balance,
+        ) {
+            let block = self.env().block_number();
+            let count = self
+                .vote_checkpoint_counts
+                .get(delegate)
+                .unwrap_or_default();
+            let last_block = count
+                .checked_sub(1)
+                .and_then(|last| self.vote_checkpoints.get((delegate, &last)))
+                .map(|(last_block, _)| last_block);
+            // Several changes within a block only keep the latest value.
+            let index = if last_block == Some(block) {
+                count - 1
+            } else {
+                self.vote_checkpoint_counts.insert(delegate, &(count + 1));
+                count
+            };
+            self.vote_checkpoints
+                .insert((delegate, &index), &(block, new_votes));
+            self.env().emit_event(DelegateVotesChanged {
+                delegate: *delegate,
+                previous_votes,
+                new_votes,
+            });
+        }
+
+        fn push_total_supply_checkpoint(&mut self, total_supply: Balance) {
+            let block = self.env().block_number();
+            let count = self.total_supply_checkpoint_count;
+            let last_block = count
+                .checked_sub(1)
+                .and_then(|last| self.total_supply_checkpoints.get(last))
+                .map(|(last_block, _)| last_block);
+            let index = if last_block == Some(block) {
+                count - 1
+            } else {
+                self.total_supply_checkpoint_count = count + 1;
+                count
+            };
+            self.total_supply_checkpoints
+                .insert(index, &(block, total_supply));
+        }
+
+        /// Records the total supply before its first change after the current snapshot.
+        fn update_total_supply_snapshot(&mut self) {
+            let id = self.current_snapshot_id;
+            if id == 0 {
+                return;
+            }
+            let count = self.total_supply_snapshot_count;
+            if count > 0 {
+                let (last_id, _) = self
+                    .total_supply_snapshots
+                    .get(count - 1)
+                    .unwrap_or_default();
+                if last_id == id {
+                    return;
+                }
+            }
+            self.total_supply_snapshots
+                .insert(count, &(id, self.total_supply));
+            self.total_supply_snapshot_count = count + 1;
+        }
+
+        #[ink(message)]
+        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            self.approve_impl(owner, spender, value)
+        }
+
+        /// Atomically increases the allowance granted to `spender` by the caller.
+        #[ink(message)]
+        pub fn increase_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            let allowance = self
+                .allowance_impl(&owner, &spender)
+                .checked_add(delta)
+                .ok_or(Error::Overflow)?;
+            self.approve_impl(owner, spender, allowance)
+        }
+
+        /// Atomically decreases the allowance granted to `spender` by the caller.
+        #[ink(message)]
+        pub fn decrease_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            let allowance = self
+                .allowance_impl(&owner, &spender)
+                .checked_sub(delta)
+                .ok_or(Error::InsufficientAllowance)?;
+            self.approve_impl(owner, spender, allowance)
+        }
+
+        /// Sets the allowance of `spender` to `new_value` only if it currently
+        /// equals `expected_current`.
+        #[ink(message)]
+        pub fn approve_if(
+            &mut self,
+            spender: AccountId,
+            expected_current: Balance,
+            new_value: Balance,
+        ) -> Result<()> {
+            let owner = self.env().caller();
+            if self.allowance_impl(&owner, &spender) != expected_current {
+                return Err(Error::AllowanceChanged);
+            }
+            self.approve_impl(owner, spender, new_value)
+        }
+
+        /// Sets the allowance of `spender` over the tokens of `owner` from an ECDSA
+        /// `signature` by `owner`, without `owner` submitting a transaction.
+        ///
+        /// The signed message is the Blake2x256 hash of the SCALE-encoded
+        /// `(domain_separator, owner, spender, value, nonce, deadline)` tuple, where
+        /// `owner` is the account derived from the signer's public key.
+        #[ink(message)]
+        pub fn permit(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            deadline: Timestamp,
+            signature: [u8; 65],
+        ) -> Result<()> {
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::PermitExpired);
+            }
+            let nonce = self.nonces(owner);
+            let message_hash = self.env().hash_encoded::<Blake2x256, _>(&(
+                self.domain_separator(),
+                owner,
+                spender,
+                value,
+                nonce,
+                deadline,
+            ));
+            let public_key = self
+                .env()
+                .ecdsa_recover(&signature, &message_hash)
+                .map_err(|_| Error::InvalidSignature)?;
+            let signer = self.env().hash_bytes::<Blake2x256>(&public_key);
+            if AccountId::from(signer) != owner {
+                return Err(Error::InvalidSignature);
+            }
+            let nonce = nonce.checked_add(1).ok_or(Error::Overflow)?;
+            self.nonces.insert(owner, &nonce);
+            self.approve_impl(owner, spender, value)
+        }
+
+        /// Returns the nonce that the next permit of `owner` must be signed with.
+        #[ink(message)]
+        pub fn nonces(&self, owner: AccountId) -> u64 {
+            self.nonces.get(owner).unwrap_or_default()
+        }
+
+        /// Returns the domain separator included in permit signatures.
+        ///
+        /// It binds signatures to this contract instance and token name.
+        #[ink(message)]
+        pub fn domain_separator(&self) -> [u8; 32] {
+            self.env()
+                .hash_encoded::<Blake2x256, _>(&(&self.name, self.env().account_id()))
+        }
+
+        fn approve_impl(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            self.ensure_not_frozen(&owner)?;
+            self.ensure_not_frozen(&spender)?;
+            self.allowances.insert((&owner, &spender), &value);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowance_impl(&owner, &spender)
+        }
+
+        #[inline]
+        fn allowance_impl(&self, owner: &AccountId, spender: &AccountId) -> Balance {
+            self.allowances.get((owner, spender)).unwrap_or_default()
+        }
+
+        /// Transfers token on the behalf of the `from` account to the `to` account.
+        #[ink(message)]
+        pub fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_not_frozen(&caller)?;
+            let allowance = self.allowance_impl(&from, &caller);
+            if allowance < value {
+                return Err(Error::InsufficientAllowance);
+            }
+            let allowance = allowance.checked_sub(value).ok_or(Error::Underflow)?;
+            self.transfer_from_to(&from, &to, value)?;
+            self.allowances.insert((&from, &caller), &allowance);
+            Ok(())
+        }
+
+        /// Transfers tokens from the caller to each `(to, value)` pair in `transfers`.
+        ///
+        /// Either every transfer succeeds or the whole batch fails.
+        ///
+        /// # Errors
+        ///
+        /// Returns `InsufficientBalance` error if the caller does not hold the total
+        /// of all values, or `BatchTransferFailed` with the index of the first entry
+        /// that could not be transferred.
+        #[ink(message)]
+        pub fn batch_transfer(&mut self, transfers: Vec<(AccountId, Balance)>) -> Result<()> {
+            let from = self.env().caller();
+            let total = Self::batch_total(&transfers)?;
+            self.batch_transfer_from_to(&from, transfers, total)
+        }
+
+        /// Transfers tokens on behalf of `from` to each `(to, value)` pair in
+        /// `transfers`, spending the caller's allowance once for the total.
+        ///
+        /// Either every transfer succeeds or the whole batch fails.
+        ///
+        /// # Errors
+        ///
+        /// Returns `InsufficientAllowance` error if the caller's allowance does not
+        /// cover the total of all values, `InsufficientBalance` error if `from` does
+        /// not hold it, or `BatchTransferFailed` with the index of the first entry
+        /// that could not be transferred.
+        #[ink(message)]
+        pub fn batch_transfer_from(
+            &mut self,
+            from: AccountId,
+            transfers: Vec<(AccountId, Balance)>,
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_not_frozen(&caller)?;
+            let total = Self::batch_total(&transfers)?;
+            let allowance = self
+                .allowance_impl(&from, &caller)
+                .checked_sub(total)
+                .ok_or(Error::InsufficientAllowance)?;
+            self.batch_transfer_from_to(&from, transfers, total)?;
+            self.allowances.insert((&from, &caller), &allowance);
+            Ok(())
+        }
+
+        /// Returns the sum of all values in `transfers`.
+        fn batch_total(transfers: &[(AccountId, Balance)]) -> Result<Balance> {
+            transfers
+                .iter()
+                .try_fold(0, |total: Balance, (_, value)| total.checked_add(*value))
+                .ok_or(Error::Overflow)
+        }
+
+        /// Checks that `from` can spend `total` and every recipient can receive its
+        /// tokens, then debits `total` from `from` once and credits each recipient.
+        ///
+        /// A failure is tagged with the index of its entry.
+        fn batch_transfer_from_to(
+            &mut self,
+            from: &AccountId,
+            transfers: Vec<(AccountId, Balance)>,
+            total: Balance,
+        ) -> Result<()> {
+            let entry_error = |index: usize| {
+                move |error| Error::BatchTransferFailed(index as u32, Box::new(error))
+            };
+            self.ensure_not_paused()?;
+            self.ensure_not_frozen(from)?;
+            let from_balance = self.balance_of_impl(from);
+            if from_balance < total {
+                return Err(Error::InsufficientBalance);
+            }
+            self.ensure_unlocked(from, from_balance, total)?;
+            let fees = transfers
+                .iter()
+                .enumerate()
+                .map(|(index, (to, value))| {
+                    self.check_recipient(from, to, *value)
+                        .map_err(entry_error(index))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            self.debit(from, total)?;
+            for (index, ((to, value), fee)) in transfers.into_iter().zip(fees).enumerate() {
+                match fee {
+                    Some((treasury, fee)) => self
+                        .credit(from, &to, value - fee)
+                        .and_then(|()| self.credit(from, &treasury, fee)),
+                    None => self.credit(from, &to, value),
+                }
+                .map_err(entry_error(index))?;
+            }
+            Ok(())
+        }
+
+        /// Transfers `value` tokens from the caller to `to`, passing `data` to the
+        /// recipient if it is a contract.
+        #[ink(message)]
+        pub fn transfer_with_data(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            let from = self.env().caller();
+            self.transfer_from_to_with_data(&from, &to, value, data)
+        }
+
+        /// Transfers token on the behalf of the `from` account to the `to` account,
+        /// passing `data` to the recipient if it is a contract.
+        #[ink(message)]
+        pub fn transfer_from_with_data(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_not_frozen(&caller)?;
+            let allowance = self.allowance_impl(&from, &caller);
+            if allowance < value {
+                return Err(Error::InsufficientAllowance);
+            }
+            let allowance = allowance.checked_sub(value).ok_or(Error::Underflow)?;
+            // Spend the allowance before the receiver hook can reenter.
+            self.allowances.insert((&from, &caller), &allowance);
+            self.transfer_from_to_with_data(&from, &to, value, data)
+        }
+
+        fn transfer_from_to_with_data(
+            &mut self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            if self.balance_of_impl(from) < value {
+                return Err(Error::InsufficientBalance);
+            }
+            self.before_received_check(from, to, value, data)?;
+            self.transfer_from_to(from, to, value)
+        }
+
+        /// Calls `PSP22Receiver::before_received` on `to` if it is a contract.
+        ///
+        /// Plain accounts always accept tokens. Contracts that reject the transfer or
+        /// do not implement the hook cause the transfer to fail.
+        fn before_received_check(
+            &mut self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            let operator = self.env().caller();
+            self.flush();
+            let result = self.call_before_received(to, operator, from, value, data);
+            self.load();
+
+            match result {
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(PSP22ReceiverError::TransferRejected(reason))) => {
+                    Err(Error::SafeTransferCheckFailed(reason))
+                }
+                // `to` is not a contract.
+                Err(ink_env::Error::NotCallable) | Err(ink_env::Error::CodeNotFound) => Ok(()),
+                Err(error) => Err(Error::SafeTransferCheckFailed(format!("{:?}", error))),
+            }
+        }
+
+        /// Dispatches `PSP22Receiver::before_received` to `to`, allowing reentry.
+        #[cfg(not(test))]
+        fn call_before_received(
+            &mut self,
+            to: &AccountId,
+            operator: AccountId,
+            from: &AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> ink_env::Result<core::result::Result<(), PSP22ReceiverError>> {
+            build_call::<Environment>()
+                .call_type(Call::new().callee(*to))
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink_lang::selector_bytes!(
+                        "PSP22Receiver::before_received"
+                    )))
+                    .push_arg(operator)
+                    .push_arg(*from)
+                    .push_arg(value)
+                    .push_arg(data),
+                )
+                .returns::<core::result::Result<(), PSP22ReceiverError>>()
+                .fire()
+        }
+
+        /// Dispatches `PSP22Receiver::before_received` to the receiver installed by
+        /// `tests::set_receiver`, as the off-chain environment cannot call contracts.
+        #[cfg(test)]
+        fn call_before_received(
+            &mut self,
+            to: &AccountId,
+            operator: AccountId,
+            from: &AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> ink_env::Result<core::result::Result<(), PSP22ReceiverError>> {
+            let result = tests::call_receiver(self, *to, operator, *from, value, data);
+            // A reentrant call persists its changes before returning.
+            self.flush();
+            result
+        }
+
+        /// Returns the maximum amount of `token` available for a flash loan.
+        #[ink(message)]
+        pub fn max_flash_loan(&self, token: AccountId) -> Balance {
+            // Flash loans mint tokens, which would be unbacked on a wrapper.
+            if token != self.env().account_id() || self.is_wrapper() {
+                return 0;
+            }
+            self.cap
+                .unwrap_or(Balance::MAX)
+                .saturating_sub(self.total_supply)
+        }
+
+        /// Returns the fee charged for a flash loan of `amount` tokens of `token`.
+        ///
+        /// Flash loans are currently free of charge.
+        #[ink(message)]
+        pub fn flash_fee(&self, token: AccountId, _amount: Balance) -> Result<Balance> {
+            if token != self.env().account_id() {
+                return Err(Error::UnsupportedToken);
+            }
+            Ok(0)
+        }
+
+        /// Lends `amount` newly minted tokens to `receiver` for the duration of its
+        /// `FlashBorrower::on_flash_loan` callback.
+        ///
+        /// Once the callback returns, the loan plus fee is burned from `receiver`
+        /// using the allowance it granted to this contract.
+        #[ink(message)]
+        pub fn flash_loan(
+            &mut self,
+            receiver: AccountId,
+            token: AccountId,
+            amount: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            let fee = self.flash_fee(token, amount)?;
+            if amount > self.max_flash_loan(token) {
+                return Err(Error::ExceededMaxFlashLoan);
+            }
+            let repayment = amount.checked_add(fee).ok_or(Error::Overflow)?;
+
+            self.mint_to(&receiver, amount)?;
+            self.on_flash_loan_check(&receiver, token, amount, fee, data)?;
+
+            let lender = self.env().account_id();
+            let allowance = self.allowance_impl(&receiver, &lender);
+            let allowance = allowance
+                .checked_sub(repayment)
+                .ok_or(Error::FlashLoanRepaymentFailed)?;
+            self.burn_from_account(&receiver, repayment)
+                .map_err(|_| Error::FlashLoanRepaymentFailed)?;
+            self.allowances.insert((&receiver, &lender), &allowance);
+            Ok(())
+        }
+
+        /// Calls `FlashBorrower::on_flash_loan` on `receiver`.
+        fn on_flash_loan_check(
+            &mut self,
+            receiver: &AccountId,
+            token: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            let initiator = self.env().caller();
+            self.flush();
+            let result = self.call_on_flash_loan(receiver, initiator, token, amount, fee, data);
+            self.load();
+
+            match result {
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(FlashBorrowerError::FlashloanRejected(reason))) => {
+                    Err(Error::FlashLoanRejected(reason))
+                }
+                Err(error) => Err(Error::FlashLoanRejected(format!("{:?}", error))),
+            }
+        }
+
+        /// Dispatches `FlashBorrower::on_flash_loan` to `receiver`, allowing reentry.
+        #[cfg(not(test))]
+        fn call_on_flash_loan(
+            &mut self,
+            receiver: &AccountId,
+            initiator: AccountId,
+            token: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: Vec<u8>,
+        ) -> ink_env::Result<core::result::Result<(), FlashBorrowerError>> {
+            build_call::<Environment>()
+                .call_type(Call::new().callee(*receiver))
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink_lang::selector_bytes!(
+                        "FlashBorrower::on_flash_loan"
+                    )))
+                    .push_arg(initiator)
+                    .push_arg(token)
+                    .push_arg(amount)
+                    .push_arg(fee)
+                    .push_arg(data),
+                )
+                .returns::<core::result::Result<(), FlashBorrowerError>>()
+                .fire()
+        }
+
+        /// Dispatches `FlashBorrower::on_flash_loan` to the borrower installed by
+        /// `tests::set_borrower`, as the off-chain environment cannot call contracts.
+        #[cfg(test)]
+        fn call_on_flash_loan(
+            &mut self,
+            receiver: &AccountId,
+            initiator: AccountId,
+            token: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: Vec<u8>,
+        ) -> ink_env::Result<core::result::Result<(), FlashBorrowerError>> {
+            let result = tests::call_borrower(self, *receiver, initiator, token, amount, fee, data);
+            // A reentrant call persists its changes before returning.
+            self.flush();
+            result
+        }
+
+        /// Returns `true` if this contract wraps native currency.
+        #[ink(message)]
+        pub fn is_native_wrapper(&self) -> bool {
+            self.native_wrapper
+        }
+
+        /// Wraps the transferred native currency into the same amount of tokens for
+        /// the caller.
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) -> Result<()> {
+            self.ensure_native_wrapper()?;
+            let account = self.env().caller();
+            let value = self.env().transferred_value();
+            self.mint_to(&account, value)?;
+            self.env().emit_event(Deposit { account, value });
+            Ok(())
+        }
+
+        /// Burns `value` tokens of the caller and sends back the same amount of
+        /// native currency.
+        #[ink(message)]
+        pub fn withdraw(&mut self, value: Balance) -> Result<()> {
+            self.ensure_native_wrapper()?;
+            let account = self.env().caller();
+            self.burn_from_account(&account, value)?;
+            self.env()
+                .transfer(account, value)
+                .map_err(|_| Error::NativeTransferFailed)?;
+            self.env().emit_event(Withdrawal { account, value });
+            Ok(())
+        }
+
+        fn ensure_native_wrapper(&self) -> Result<()> {
+            if !self.native_wrapper {
+                return Err(Error::NotNativeWrapper);
+            }
+            Ok(())
+        }
+
+        /// Returns the token wrapped by this contract, if any.
+        #[ink(message)]
+        pub fn underlying(&self) -> Option<AccountId> {
+            self.underlying
+        }
+
+        /// Takes `value` underlying tokens from the caller and mints the same amount
+        /// of wrapper tokens to `account`.
+        ///
+        /// The caller must have approved this contract on the underlying token.
+        #[ink(message)]
+        pub fn deposit_for(&mut self, account: AccountId, value: Balance) -> Result<()> {
+            let underlying = self.underlying.ok_or(Error::NotWrapper)?;
+            let caller = self.env().caller();
+            self.underlying_transfer_from(underlying, caller, self.env().account_id(), value)?;
+            self.mint_to(&account, value)
+        }
+
+        /// Burns `value` wrapper tokens of the caller and sends the same amount of
+        /// underlying tokens to `account`.
+        #[ink(message)]
+        pub fn withdraw_to(&mut self, account: AccountId, value: Balance) -> Result<()> {
+            let underlying = self.underlying.ok_or(Error::NotWrapper)?;
+            let caller = self.env().caller();
+            self.burn_from_account(&caller, value)?;
+            self.underlying_transfer(underlying, account, value)
+        }
+
+        /// Mints wrapper tokens for any underlying tokens sent to this contract
+        /// directly to the caller and returns the minted amount.
+        ///
+        /// Requires `DEFAULT_ADMIN_ROLE`.
+        #[ink(message)]
+        pub fn recover(&mut self) -> Result<Balance> {
+            let underlying = self.underlying.ok_or(Error::NotWrapper)?;
+            self.ensure_role(DEFAULT_ADMIN_ROLE)?;
+            let surplus = self
+                .underlying_balance_of(underlying, self.env().account_id())
+                .saturating_sub(self.total_supply);
+            let caller = self.env().caller();
+            self.mint_to(&caller, surplus)?;
+            Ok(surplus)
+        }
+
+        /// Returns `true` if the supply is backed by native currency or an
+        /// underlying token.
+        fn is_wrapper(&self) -> bool {
+            self.native_wrapper || self.underlying.is_some()
+        }
+
+        /// Calls `transfer_from` on the `underlying` token.
+        #[cfg(not(test))]
+        fn underlying_transfer_from(
+            &mut self,
+            underlying: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let mut underlying: Erc20Ref = FromAccountId::from_account_id(underlying);
+            underlying.transfer_from(from, to, value)
+        }
+
+        /// Moves underlying tokens in the ledger of `tests::set_underlying_balance`,
+        /// as the off-chain environment cannot call contracts.
+        #[cfg(test)]
+        fn underlying_transfer_from(
+            &mut self,
+            _underlying: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            tests::underlying_transfer(from, to, value)
+        }
+
+        /// Calls `transfer` on the `underlying` token.
+        #[cfg(not(test))]
+        fn underlying_transfer(
+            &mut self,
+            underlying: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let mut underlying: Erc20Ref = FromAccountId::from_account_id(underlying);
+            underlying.transfer(to, value)
+        }
+
+        /// Moves underlying tokens of this contract in the ledger of
+        /// `tests::set_underlying_balance`.
+        #[cfg(test)]
+        fn underlying_transfer(
+            &mut self,
+            _underlying: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            tests::underlying_transfer(self.env().account_id(), to, value)
+        }
+
+        /// Calls `balance_of` on the `underlying` token.
+        #[cfg(not(test))]
+        fn underlying_balance_of(&self, underlying: AccountId, owner: AccountId) -> Balance {
+            let underlying: Erc20Ref = FromAccountId::from_account_id(underlying);
+            underlying.balance_of(owner)
+        }
+
+        /// Reads the ledger of `tests::set_underlying_balance`.
+        #[cfg(test)]
+        fn underlying_balance_of(&self, _underlying: AccountId, owner: AccountId) -> Balance {
+            tests::underlying_balance_of(owner)
+        }
+
+        /// Locks `amount` tokens of the caller in a vesting schedule for `beneficiary`
+        /// and returns the index of the schedule.
+        ///
+        /// Tokens vest linearly from `start` to `start + duration`, with nothing
+        /// vested before `start + cliff`. The locked tokens are held by this contract.
+        ///
+        /// Requires `DEFAULT_ADMIN_ROLE`.
+        #[ink(message)]
+        pub fn create_vesting(
+            &mut self,
+            beneficiary: AccountId,
+            amount: Balance,
+            start: Timestamp,
+            cliff: Timestamp,
+            duration: Timestamp,
+            revocable: bool,
+        ) -> Result<u32> {
+            self.ensure_role(DEFAULT_ADMIN_ROLE)?;
+            if amount == 0 || duration == 0 || cliff > duration {
+                return Err(Error::InvalidVestingSchedule);
+            }
+            let caller = self.env().caller();
+            self.transfer_from_to(&caller, &self.env().account_id(), amount)?;
+
+            let index = self
+                .vesting_schedule_counts
+                .get(beneficiary)
+                .unwrap_or_default();
+            let schedule = VestingSchedule {
+                amount,
+                released: 0,
+                start,
+                cliff,
+                duration,
+                revocable,
+                revoked: false,
+            };
+            self.vesting_schedules
+                .insert((beneficiary, index), &schedule);
+            self.vesting_schedule_counts
+                .insert(beneficiary, &(index + 1));
+            self.env().emit_event(VestingCreated {
+                beneficiary,
+                index,
+                amount,
+            });
+            Ok(index)
+        }
+
+        /// Returns the vesting schedule of `beneficiary` at `index`, if any.
+        #[ink(message)]
+        pub fn vesting_schedule(
+            &self,
+            beneficiary: AccountId,
+            index: u32,
+        ) -> Option<VestingSchedule> {
+            self.vesting_schedules.get((beneficiary, index))
+        }
+
+        /// Returns the number of vesting schedules of `beneficiary`.
+        #[ink(message)]
+        pub fn vesting_schedule_count(&self, beneficiary: AccountId) -> u32 {
+            self.vesting_schedule_counts
+                .get(beneficiary)
+                .unwrap_or_default()
+        }
+
+        /// Returns the amount of vested tokens `beneficiary` has not released yet.
+        #[ink(message)]
+        pub fn releasable(&self, beneficiary: AccountId) -> Balance {
+            let now = self.env().block_timestamp();
+            (0..self.vesting_schedule_count(beneficiary))
+                .filter_map(|index| self.vesting_schedules.get((beneficiary, index)))
+                .map(|schedule| schedule.vested_amount(now) - schedule.released)
+                .fold(0, Balance::saturating_add)
+        }
+
+        /// Transfers all vested tokens of the caller to the caller and returns the
+        /// released amount. Nothing is transferred or emitted if no tokens are vested.
+        #[ink(message)]
+        pub fn release(&mut self) -> Result<Balance> {
+            let beneficiary = self.env().caller();
+            let now = self.env().block_timestamp();
+            let mut amount: Balance = 0;
+            for index in 0..self.vesting_schedule_count(beneficiary) {
+                let mut schedule = match self.vesting_schedules.get((beneficiary, index)) {
+                    Some(schedule) => schedule,
+                    None => continue,
+                };
+                let vested = schedule.vested_amount(now);
+                if vested == schedule.released {
+                    continue;
+                }
+                amount = amount
+                    .checked_add(vested - schedule.released)
+                    .ok_or(Error::Overflow)?;
+                schedule.released = vested;
+                self.vesting_schedules
+                    .insert((beneficiary, index), &schedule);
+            }
+            if amount == 0 {
+                return Ok(0);
+            }
+            self.transfer_from_to(&self.env().account_id(), &beneficiary, amount)?;
+            self.env().emit_event(VestingReleased {
+                beneficiary,
+                amount,
+            });
+            Ok(amount)
+        }
+
+        /// Revokes the vesting schedule of `beneficiary` at `index`, returning the
+        /// unvested tokens to the caller. Already vested tokens stay releasable.
+        ///
+        /// Requires `DEFAULT_ADMIN_ROLE`.
+        #[ink(message)]
+        pub fn revoke_vesting(&mut self, beneficiary: AccountId, index: u32) -> Result<Balance> {
+            self.ensure_role(DEFAULT_ADMIN_ROLE)?;
+            let mut schedule = self
+                .vesting_schedules
+                .get((beneficiary, index))
+                .ok_or(Error::VestingScheduleNotFound)?;
+            if !schedule.revocable || schedule.revoked {
+                return Err(Error::VestingNotRevocable);
+            }
+            let vested = schedule.vested_amount(self.env().block_timestamp());
+            let refund = schedule.amount - vested;
+            schedule.amount = vested;
+            schedule.revoked = true;
+            self.vesting_schedules
+                .insert((beneficiary, index), &schedule);
+
+            let caller = self.env().caller();
+            self.transfer_from_to(&self.env().account_id(), &caller, refund)?;
+            self.env().emit_event(VestingRevoked {
+                beneficiary,
+                index,
+                refund,
+            });
+            Ok(refund)
+        }
+
+        /// Writes the in-memory contract state to storage before a reentrant call.
+        fn flush(&self) {
+            push_spread_root::<Self>(self, &Key::from([0x00; 32]));
+        }
+
+        /// Reloads the contract state from storage after a reentrant call.
+        fn load(&mut self) {
+            *self = pull_spread_root::<Self>(&Key::from([0x00; 32]));
+        }
+    }
+
+    impl PSP22 for Erc20 {
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.total_supply
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balance_of_impl(&owner)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowance_impl(&owner, &spender)
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> core::result::Result<(), PSP22Error> {
+            Ok(self.transfer_with_data(to, value, data)?)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> core::result::Result<(), PSP22Error> {
+            Ok(self.transfer_from_with_data(from, to, value, data)?)
+        }
+
+        #[ink(message)]
+        fn approve(
+            &mut self,
+            spender: AccountId,
+            value: Balance,
+        ) -> core::result::Result<(), PSP22Error> {
+            Ok(Erc20::approve(self, spender, value)?)
+        }
+
+        #[ink(message)]
+        fn increase_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: Balance,
+        ) -> core::result::Result<(), PSP22Error> {
+            Ok(Erc20::increase_allowance(self, spender, delta_value)?)
+        }
+
+        #[ink(message)]
+        fn decrease_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: Balance,
+        ) -> core::result::Result<(), PSP22Error> {
+            Ok(Erc20::decrease_allowance(self, spender, delta_value)?)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        use ink_lang as ink;
+        use std::{cell::RefCell, collections::HashMap};
+
+        type ReceiverResult = ink_env::Result<core::result::Result<(), PSP22ReceiverError>>;
+        type Receiver =
+            Box<dyn FnMut(&mut Erc20, AccountId, AccountId, AccountId, Balance) -> ReceiverResult>;
+
+        type BorrowerResult = ink_env::Result<core::result::Result<(), FlashBorrowerError>>;
+        type Borrower = Box<
+            dyn FnMut(
+                &mut Erc20,
+                AccountId,
+                AccountId,
+                AccountId,
+                Balance,
+                Balance,
+            ) -> BorrowerResult,
+        >;
+
+        thread_local! {
+            static RECEIVER: RefCell<Option<Receiver>> = RefCell::new(None);
+            static BORROWER: RefCell<Option<Borrower>> = RefCell::new(None);
+            static UNDERLYING: RefCell<HashMap<AccountId, Balance>> = RefCell::new(HashMap::new());
+        }
+
+        /// Makes `receiver` answer `PSP22Receiver::before_received` calls with the
+        /// contract, recipient, operator, sender and value.
+        fn set_receiver<F>(receiver: F)
+        where
+            F: FnMut(&mut Erc20, AccountId, AccountId, AccountId, Balance) -> ReceiverResult
+                + 'static,
+        {
+            RECEIVER.with(|cell| *cell.borrow_mut() = Some(Box::new(receiver)));
+        }
+
+        /// Calls the receiver installed by `set_receiver`. Without one, the recipient
+        /// is a plain account.
+        pub(super) fn call_receiver(
+            contract: &mut Erc20,
+            to: AccountId,
+            operator: AccountId,
+            from: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> ReceiverResult {
+            // Taken out for the call so that the receiver can reenter the contract.
+            let receiver = RECEIVER.with(|cell| cell.borrow_mut().take());
+            match receiver {
+                Some(mut receiver) => {
+                    let result = receiver(contract, to, operator, from, value);
+                    RECEIVER.with(|cell| *cell.borrow_mut() = Some(receiver));
+                    result
+                }
+                None => Err(ink_env::Error::CodeNotFound),
+            }
+        }
+
+        /// Makes `borrower` answer `FlashBorrower::on_flash_loan` calls with the
+        /// contract, receiver, initiator, token, amount and fee.
+        fn set_borrower<F>(borrower: F)
+        where
+            F: FnMut(
+                    &mut Erc20,
+                    AccountId,
+                    AccountId,
+                    AccountId,
+                    Balance,
+                    Balance,
+                ) -> BorrowerResult
+                + 'static,
+        {
+            BORROWER.with(|cell| *cell.borrow_mut() = Some(Box::new(borrower)));
+        }
+
+        /// Calls the borrower installed by `set_borrower`. Without one, the receiver
+        /// is a plain account.
+        pub(super) fn call_borrower(
+            contract: &mut Erc20,
+            receiver: AccountId,
+            initiator: AccountId,
+            token: AccountId,
+            amount: Balance,
+            fee: Balance,
+            _data: Vec<u8>,
+        ) -> BorrowerResult {
+            // Taken out for the call so that the borrower can reenter the contract.
+            let borrower = BORROWER.with(|cell| cell.borrow_mut().take());
+            match borrower {
+                Some(mut borrower) => {
+                    let result = borrower(contract, receiver, initiator, token, amount, fee);
+                    BORROWER.with(|cell| *cell.borrow_mut() = Some(borrower));
+                    result
+                }
+                None => Err(ink_env::Error::NotCallable),
+            }
+        }
+
+        /// Sets the balance of `owner` in the ledger standing in for the underlying
+        /// token of a wrapper.
+        fn set_underlying_balance(owner: AccountId, value: Balance) {
+            UNDERLYING.with(|cell| cell.borrow_mut().insert(owner, value));
+        }
+
+        pub(super) fn underlying_balance_of(owner: AccountId) -> Balance {
+            UNDERLYING.with(|cell| cell.borrow().get(&owner).copied().unwrap_or(0))
+        }
+
+        /// Moves `value` underlying tokens from `from` to `to`. Allowances are not
+        /// tracked.
+        pub(super) fn underlying_transfer(
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let from_balance = underlying_balance_of(from);
+            if from_balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            set_underlying_balance(from, from_balance - value);
+            set_underlying_balance(to, underlying_balance_of(to) + value);
+            Ok(())
+        }
+
+        #[ink::test]
+        fn mul_div_floor_does_not_overflow() {
+            assert_eq!(mul_div_floor(1_999, 1, 10_000), 0);
+            assert_eq!(mul_div_floor(20_001, 25, 100), 5_000);
+            assert_eq!(mul_div_floor(Balance::MAX, 10_000, 10_000), Balance::MAX);
+            assert_eq!(mul_div_floor(Balance::MAX, 1, 3), Balance::MAX / 3);
+            assert_eq!(
+                mul_div_floor(Balance::MAX, u64::MAX.into(), u64::MAX.into()),
+                Balance::MAX
+            );
+        }
+
+        #[ink::test]
+        fn new_works() {
+            let contract = Erc20::new(777);
+            assert_eq!(contract.total_supply(), 777);
+        }
+
+        #[ink::test]
+        fn new_without_metadata_works() {
+            let contract = Erc20::new(100);
+            assert_eq!(contract.token_name(), None);
+            assert_eq!(contract.token_symbol(), None);
+            assert_eq!(contract.token_decimals(), 0);
+        }
+
+        #[ink::test]
+        fn new_with_metadata_works() {
+            let contract = Erc20::new_with_metadata(
+                100,
+                Some(String::from("Phonbopit Token")),
+                Some(String::from("PBT")),
+                18,
+            );
+            assert_eq!(contract.total_supply(), 100);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
+            assert_eq!(contract.token_name(), Some(String::from("Phonbopit Token")));
+            assert_eq!(contract.token_symbol(), Some(String::from("PBT")));
+            assert_eq!(contract.token_decimals(), 18);
+        }
+
+        #[ink::test]
+        fn balance_works() {
+            let contract = Erc20::new(100);
+            assert_eq!(contract.total_supply(), 100);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
+            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 0);
+        }
+
+        #[ink::test]
+        fn transfer_works() {
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(erc20.balance_of(AccountId::from([0x0; 32])), 0);
+            assert_eq!(erc20.transfer(AccountId::from([0x0; 32]), 10), Ok(()));
+            assert_eq!(erc20.balance_of(AccountId::from([0x0; 32])), 10);
+        }
+
+        #[ink::test]
+        fn mint_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.owner(), Some(accounts.alice));
+
+            assert_eq!(contract.mint(accounts.bob, 50), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 50);
+            assert_eq!(contract.total_supply(), 150);
+
+            // Initial transfer, ownership, five seeded roles and the mint.
+            let emitted_events = ink_env::test::recorded_events().count();
+            assert_eq!(emitted_events, 8);
+        }
+
+        #[ink::test]
+        fn mint_fails_without_minter_role() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.mint(accounts.bob, 50), Err(Error::MissingRole));
+            assert_eq!(contract.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn mint_fails_on_overflow() {
+            let mut contract = Erc20::new(Balance::MAX);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.mint(accounts.bob, 1), Err(Error::Overflow));
+            assert_eq!(contract.total_supply(), Balance::MAX);
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn balances_at_max_do_not_wrap() {
+            let mut contract = Erc20::new(Balance::MAX);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Move the whole supply to Bob, then back to Alice.
+            assert_eq!(contract.transfer(accounts.bob, Balance::MAX), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), Balance::MAX);
+            assert_eq!(contract.mint(accounts.bob, 1), Err(Error::Overflow));
+            assert_eq!(contract.mint(accounts.alice, 1), Err(Error::Overflow));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.bob, Balance::MAX), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), Balance::MAX);
+            assert_eq!(contract.transfer(accounts.alice, Balance::MAX), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), Balance::MAX);
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.total_supply(), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn allowance_at_max_is_consumed_without_wrapping() {
+            let mut contract = Erc20::new(Balance::MAX);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.approve(accounts.bob, Balance::MAX), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, Balance::MAX),
+                Ok(())
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 1),
+                Err(Error::InsufficientAllowance)
+            );
+
+            assert_eq!(contract.burn(Balance::MAX), Ok(()));
+            assert_eq!(contract.total_supply(), 0);
+            assert_eq!(contract.burn(1), Err(Error::InsufficientBalance));
+        }
+
+        #[ink::test]
+        fn increase_allowance_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.increase_allowance(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.increase_allowance(accounts.bob, 15), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 25);
+            assert_eq!(
+                contract.increase_allowance(accounts.bob, Balance::MAX),
+                Err(Error::Overflow)
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 25);
+            assert_eq!(ink_env::test::recorded_events().count(), 9);
+        }
+
+        #[ink::test]
+        fn decrease_allowance_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.approve(accounts.bob, 30), Ok(()));
+
+            assert_eq!(contract.decrease_allowance(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 20);
+            assert_eq!(
+                contract.decrease_allowance(accounts.bob, 21),
+                Err(Error::InsufficientAllowance)
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 20);
+            assert_eq!(contract.decrease_allowance(accounts.bob, 20), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn approve_if_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.approve_if(accounts.bob, 0, 20), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 20);
+
+            // Bob spends part of the allowance before Alice changes it.
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 5),
+                Ok(())
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                contract.approve_if(accounts.bob, 20, 50),
+                Err(Error::AllowanceChanged)
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 15);
+            assert_eq!(contract.approve_if(accounts.bob, 15, 50), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 50);
+        }
+
+        #[ink::test]
+        fn psp22_transfer_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(PSP22::total_supply(&contract), 100);
+            assert_eq!(
+                PSP22::transfer(&mut contract, accounts.bob, 10, Vec::new()),
+                Ok(())
+            );
+            assert_eq!(PSP22::balance_of(&contract, accounts.bob), 10);
+            assert_eq!(
+                PSP22::transfer(&mut contract, accounts.bob, 100, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
+            );
+        }
+
+        #[ink::test]
+        fn psp22_transfer_from_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(PSP22::approve(&mut contract, accounts.bob, 10), Ok(()));
+            assert_eq!(
+                PSP22::increase_allowance(&mut contract, accounts.bob, 5),
+                Ok(())
+            );
+            assert_eq!(
+                PSP22::decrease_allowance(&mut contract, accounts.bob, 3),
+                Ok(())
+            );
+            assert_eq!(
+                PSP22::allowance(&contract, accounts.alice, accounts.bob),
+                12
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                PSP22::transfer_from(
+                    &mut contract,
+                    accounts.alice,
+                    accounts.frank,
+                    13,
+                    Vec::new()
+                ),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(
+                PSP22::transfer_from(
+                    &mut contract,
+                    accounts.alice,
+                    accounts.frank,
+                    12,
+                    Vec::new()
+                ),
+                Ok(())
+            );
+            assert_eq!(PSP22::balance_of(&contract, accounts.frank), 12);
+        }
+
+        #[ink::test]
+        fn transfer_with_data_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                contract.transfer_with_data(accounts.bob, 10, Vec::from([0x1, 0x2])),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 10);
+            assert_eq!(
+                contract.transfer_with_data(accounts.bob, 91, Vec::new()),
+                Err(Error::InsufficientBalance)
+            );
+        }
+
+        #[ink::test]
+        fn transfer_from_with_data_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.approve(accounts.bob, 10), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_from_with_data(accounts.alice, accounts.frank, 11, Vec::new()),
+                Err(Error::InsufficientAllowance)
+            );
+            assert_eq!(
+                contract.transfer_from_with_data(accounts.alice, accounts.frank, 10, Vec::new()),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.frank), 10);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn receiver_can_reject_transfers() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_receiver(|_, _, _, _, _| {
+                Ok(Err(PSP22ReceiverError::TransferRejected(String::from(
+                    "no",
+                ))))
+            });
+            assert_eq!(
+                contract.transfer_with_data(accounts.bob, 10, Vec::new()),
+                Err(Error::SafeTransferCheckFailed(String::from("no")))
+            );
+            set_receiver(|_, _, _, _, _| Err(ink_env::Error::CalleeTrapped));
+            assert_eq!(
+                contract.transfer_with_data(accounts.bob, 10, Vec::new()),
+                Err(Error::SafeTransferCheckFailed(String::from(
+                    "CalleeTrapped"
+                )))
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+
+            // Plain accounts always accept tokens.
+            set_receiver(|_, _, _, _, _| Err(ink_env::Error::NotCallable));
+            assert_eq!(
+                contract.transfer_with_data(accounts.bob, 10, Vec::new()),
+                Ok(())
+            );
+            set_receiver(|_, _, _, _, _| Ok(Ok(())));
+            assert_eq!(
+                contract.transfer_with_data(accounts.bob, 10, Vec::new()),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 20);
+        }
+
+        #[ink::test]
+        fn transfer_from_with_data_spends_allowance_before_receiver() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.approve(accounts.bob, 10), Ok(()));
+
+            // Bob's receiver tries to spend the same allowance again.
+            set_receiver(|contract, to, _, from, value| {
+                assert_eq!(
+                    contract.transfer_from(from, to, value),
+                    Err(Error::InsufficientAllowance)
+                );
+                Ok(Ok(()))
+            });
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_from_with_data(accounts.alice, accounts.bob, 10, Vec::new()),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 10);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn psp22_error_conversion_works() {
+            assert_eq!(
+                PSP22Error::from(Error::InsufficientBalance),
+                PSP22Error::InsufficientBalance
+            );
+            assert_eq!(
+                PSP22Error::from(Error::NotOwner),
+                PSP22Error::Custom(String::from("NotOwner"))
+            );
+            assert_eq!(
+                PSP22Error::from(Error::SafeTransferCheckFailed(String::from("rejected"))),
+                PSP22Error::SafeTransferCheckFailed(String::from("rejected"))
+            );
+        }
+
+        #[ink::test]
+        fn pause_blocks_transfers() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.approve(accounts.bob, 10), Ok(()));
+
+            assert_eq!(contract.pause(), Ok(()));
+            assert!(contract.paused());
+            assert_eq!(contract.pause(), Err(Error::Paused));
+            assert_eq!(contract.transfer(accounts.bob, 10), Err(Error::Paused));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 10),
+                Err(Error::Paused)
+            );
+
+            // Read-only messages keep working.
+            assert_eq!(contract.balance_of(accounts.alice), 100);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 10);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.unpause(), Ok(()));
+            assert!(!contract.paused());
+            assert_eq!(contract.unpause(), Err(Error::NotPaused));
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn pause_fails_without_pauser_role() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.pause(), Err(Error::MissingRole));
+            assert!(!contract.paused());
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.pause(), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.unpause(), Err(Error::MissingRole));
+            assert!(contract.paused());
+        }
+
+        #[ink::test]
+        fn deployer_is_seeded_with_roles() {
+            let contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert!(contract.has_role(DEFAULT_ADMIN_ROLE, accounts.alice));
+            assert!(contract.has_role(MINTER_ROLE, accounts.alice));
+            assert!(contract.has_role(PAUSER_ROLE, accounts.alice));
+            assert!(!contract.has_role(MINTER_ROLE, accounts.bob));
+            assert_eq!(contract.get_role_admin(MINTER_ROLE), DEFAULT_ADMIN_ROLE);
+        }
+
+        #[ink::test]
+        fn constructor_state_survives_reload() {
+            let contract = Erc20::new_with_metadata(100, Some(String::from("Token")), None, 18);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            contract.flush();
+
+            // Messages read the state the way it is pulled here.
+            let contract = pull_spread_root::<Erc20>(&Key::from([0x00; 32]));
+            assert_eq!(contract.balance_of(accounts.alice), 100);
+            assert!(contract.has_role(DEFAULT_ADMIN_ROLE, accounts.alice));
+            assert!(contract.has_role(MINTER_ROLE, accounts.alice));
+            assert_eq!(contract.token_name(), Some(String::from("Token")));
+            assert_eq!(contract.owner(), Some(accounts.alice));
+        }
+
+        #[ink::test]
+        fn grant_and_revoke_role_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.grant_role(MINTER_ROLE, accounts.bob), Ok(()));
+            assert!(contract.has_role(MINTER_ROLE, accounts.bob));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.mint(accounts.bob, 10), Ok(()));
+            assert_eq!(
+                contract.grant_role(MINTER_ROLE, accounts.charlie),
+                Err(Error::MissingRole)
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.revoke_role(MINTER_ROLE, accounts.bob), Ok(()));
+            assert!(!contract.has_role(MINTER_ROLE, accounts.bob));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.mint(accounts.bob, 10), Err(Error::MissingRole));
+        }
+
+        #[ink::test]
+        fn set_role_admin_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.get_role_admin(MINTER_ROLE), DEFAULT_ADMIN_ROLE);
+
+            assert_eq!(contract.set_role_admin(MINTER_ROLE, PAUSER_ROLE), Ok(()));
+            assert_eq!(contract.get_role_admin(MINTER_ROLE), PAUSER_ROLE);
+            assert_eq!(contract.revoke_role(PAUSER_ROLE, accounts.alice), Ok(()));
+            assert_eq!(
+                contract.grant_role(MINTER_ROLE, accounts.bob),
+                Err(Error::MissingRole)
+            );
+            assert_eq!(
+                contract.set_role_admin(MINTER_ROLE, DEFAULT_ADMIN_ROLE),
+                Err(Error::MissingRole)
+            );
+
+            assert_eq!(contract.grant_role(PAUSER_ROLE, accounts.bob), Ok(()));
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.grant_role(MINTER_ROLE, accounts.charlie), Ok(()));
+            assert!(contract.has_role(MINTER_ROLE, accounts.charlie));
+        }
+
+        #[ink::test]
+        fn renounce_role_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                contract.renounce_role(PAUSER_ROLE, accounts.bob),
+                Err(Error::InvalidCaller)
+            );
+            assert_eq!(contract.renounce_role(PAUSER_ROLE, accounts.alice), Ok(()));
+            assert!(!contract.has_role(PAUSER_ROLE, accounts.alice));
+            assert_eq!(contract.pause(), Err(Error::MissingRole));
+        }
+
+        #[ink::test]
+        fn two_step_ownership_transfer_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.transfer_ownership(accounts.bob), Ok(()));
+            assert_eq!(contract.owner(), Some(accounts.alice));
+            assert_eq!(contract.pending_owner(), Some(accounts.bob));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.accept_ownership(), Ok(()));
+            assert_eq!(contract.owner(), Some(accounts.bob));
+            assert_eq!(contract.pending_owner(), None);
+        }
+
+        #[ink::test]
+        fn ownership_transfer_moves_admin_role() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer_ownership(accounts.bob), Ok(()));
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.accept_ownership(), Ok(()));
+
+            assert!(contract.has_role(DEFAULT_ADMIN_ROLE, accounts.bob));
+            assert!(!contract.has_role(DEFAULT_ADMIN_ROLE, accounts.alice));
+            assert_eq!(contract.freeze(accounts.charlie), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.unfreeze(accounts.charlie), Err(Error::MissingRole));
+            assert_eq!(
+                contract.grant_role(DEFAULT_ADMIN_ROLE, accounts.alice),
+                Err(Error::MissingRole)
+            );
+        }
+
+        #[ink::test]
+        fn accept_ownership_fails_for_other_account() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.accept_ownership(), Err(Error::NotPendingOwner));
+            assert_eq!(contract.transfer_ownership(accounts.bob), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(contract.accept_ownership(), Err(Error::NotPendingOwner));
+            assert_eq!(contract.owner(), Some(accounts.alice));
+        }
+
+        #[ink::test]
+        fn transfer_ownership_fails_for_non_owner() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_ownership(accounts.bob),
+                Err(Error::NotOwner)
+            );
+            assert_eq!(contract.cancel_ownership_transfer(), Err(Error::NotOwner));
+            assert_eq!(contract.renounce_ownership(), Err(Error::NotOwner));
+            assert_eq!(contract.pending_owner(), None);
+        }
+
+        #[ink::test]
+        fn cancel_ownership_transfer_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                contract.cancel_ownership_transfer(),
+                Err(Error::NoPendingOwner)
+            );
+            assert_eq!(contract.transfer_ownership(accounts.bob), Ok(()));
+            assert_eq!(contract.cancel_ownership_transfer(), Ok(()));
+            assert_eq!(contract.pending_owner(), None);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.accept_ownership(), Err(Error::NotPendingOwner));
+        }
+
+        #[ink::test]
+        fn renounce_ownership_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.transfer_ownership(accounts.bob), Ok(()));
+            assert_eq!(contract.renounce_ownership(), Ok(()));
+            assert_eq!(contract.owner(), None);
+            assert_eq!(contract.pending_owner(), None);
+            assert!(!contract.has_role(DEFAULT_ADMIN_ROLE, accounts.alice));
+            assert_eq!(
+                contract.transfer_ownership(accounts.alice),
+                Err(Error::NotOwner)
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.accept_ownership(), Err(Error::NotPendingOwner));
+        }
+
+        /// Signs a permit for the account derived from `secret_key`.
+        fn sign_permit(
+            contract: &Erc20,
+            secret_key: [u8; 32],
+            spender: AccountId,
+            value: Balance,
+            deadline: Timestamp,
+        ) -> (AccountId, [u8; 65]) {
+            use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+
+            let secp = Secp256k1::new();
+            let secret_key = SecretKey::from_slice(&secret_key).unwrap();
+            let public_key = PublicKey::from_secret_key(&secp, &secret_key).serialize();
+            let mut owner = [0x0; 32];
+            ink_env::hash_bytes::<Blake2x256>(&public_key, &mut owner);
+            let owner = AccountId::from(owner);
+
+            let mut message_hash = [0x0; 32];
+            ink_env::hash_encoded::<Blake2x256, _>(
+                &(
+                    contract.domain_separator(),
+                    owner,
+                    spender,
+                    value,
+                    contract.nonces(owner),
+                    deadline,
+                ),
+                &mut message_hash,
+            );
+            let (recovery_id, compact) = secp
+                .sign_ecdsa_recoverable(&Message::from_slice(&message_hash).unwrap(), &secret_key)
+                .serialize_compact();
+            let mut signature = [0x0; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = recovery_id.to_i32() as u8;
+            (owner, signature)
+        }
+
+        #[ink::test]
+        fn permit_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let (owner, signature) = sign_permit(&contract, [0x11; 32], accounts.bob, 50, 1_000);
+
+            assert_eq!(contract.nonces(owner), 0);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                contract.permit(owner, accounts.bob, 50, 1_000, signature),
+                Ok(())
+            );
+            assert_eq!(contract.allowance(owner, accounts.bob), 50);
+            assert_eq!(contract.nonces(owner), 1);
+
+            // The same signature cannot be replayed.
+            assert_eq!(
+                contract.permit(owner, accounts.bob, 50, 1_000, signature),
+                Err(Error::InvalidSignature)
+            );
+        }
+
+        #[ink::test]
+        fn permit_fails_with_wrong_signer() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let (owner, _) = sign_permit(&contract, [0x11; 32], accounts.bob, 50, 1_000);
+            let (_, signature) = sign_permit(&contract, [0x22; 32], accounts.bob, 50, 1_000);
+
+            assert_eq!(
+                contract.permit(owner, accounts.bob, 50, 1_000, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(
+                contract.permit(owner, accounts.bob, 60, 1_000, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(contract.allowance(owner, accounts.bob), 0);
+            assert_eq!(contract.nonces(owner), 0);
+        }
+
+        #[ink::test]
+        fn permit_fails_after_deadline() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let (owner, signature) = sign_permit(&contract, [0x11; 32], accounts.bob, 50, 0);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                contract.permit(owner, accounts.bob, 50, 0, signature),
+                Err(Error::PermitExpired)
+            );
+            assert_eq!(contract.allowance(owner, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn snapshot_records_balances() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                contract.balance_of_at(accounts.alice, 1),
+                Err(Error::InvalidSnapshotId)
+            );
+            assert_eq!(contract.snapshot(), Ok(1));
+            assert_eq!(contract.transfer(accounts.bob, 30), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.snapshot(), Ok(2));
+            assert_eq!(contract.transfer(accounts.bob, 20), Ok(()));
+            assert_eq!(contract.snapshot(), Ok(3));
+
+            assert_eq!(contract.balance_of_at(accounts.alice, 1), Ok(100));
+            assert_eq!(contract.balance_of_at(accounts.bob, 1), Ok(0));
+            assert_eq!(contract.balance_of_at(accounts.alice, 2), Ok(60));
+            assert_eq!(contract.balance_of_at(accounts.bob, 2), Ok(40));
+            assert_eq!(contract.balance_of_at(accounts.alice, 3), Ok(40));
+            assert_eq!(contract.balance_of_at(accounts.bob, 3), Ok(60));
+            assert_eq!(contract.balance_of_at(accounts.charlie, 3), Ok(0));
+            assert_eq!(
+                contract.balance_of_at(accounts.alice, 4),
+                Err(Error::InvalidSnapshotId)
+            );
+        }
+
+        #[ink::test]
+        fn snapshot_records_total_supply() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.snapshot(), Ok(1));
+            assert_eq!(contract.mint(accounts.bob, 50), Ok(()));
+            assert_eq!(contract.snapshot(), Ok(2));
+            assert_eq!(contract.burn(30), Ok(()));
+
+            assert_eq!(contract.total_supply_at(1), Ok(100));
+            assert_eq!(contract.total_supply_at(2), Ok(150));
+            assert_eq!(contract.total_supply(), 120);
+            assert_eq!(contract.balance_of_at(accounts.bob, 1), Ok(0));
+            assert_eq!(contract.balance_of_at(accounts.bob, 2), Ok(50));
+            assert_eq!(contract.balance_of_at(accounts.alice, 2), Ok(100));
+            assert_eq!(contract.total_supply_at(0), Err(Error::InvalidSnapshotId));
+        }
+
+        #[ink::test]
+        fn snapshot_fails_without_snapshot_role() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.snapshot(), Err(Error::MissingRole));
+        }
+
+        #[ink::test]
+        fn delegate_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.delegates(accounts.alice), None);
+            assert_eq!(contract.get_votes(accounts.alice), 0);
+            assert_eq!(contract.delegate(accounts.alice), Ok(()));
+            assert_eq!(contract.delegates(accounts.alice), Some(accounts.alice));
+            assert_eq!(contract.get_votes(accounts.alice), 100);
+
+            assert_eq!(contract.delegate(accounts.bob), Ok(()));
+            assert_eq!(contract.get_votes(accounts.alice), 0);
+            assert_eq!(contract.get_votes(accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn transfers_move_votes_between_delegates() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.delegate(accounts.alice), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+            // Tokens of an account without delegate carry no votes.
+            assert_eq!(contract.transfer(accounts.bob, 30), Ok(()));
+            assert_eq!(contract.get_votes(accounts.alice), 70);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.delegate(accounts.charlie), Ok(()));
+            assert_eq!(contract.get_votes(accounts.charlie), 30);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.transfer(accounts.bob, 20), Ok(()));
+            assert_eq!(contract.get_votes(accounts.alice), 50);
+            assert_eq!(contract.get_votes(accounts.charlie), 50);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.get_past_votes(accounts.alice, 0), Ok(100));
+            assert_eq!(contract.get_past_votes(accounts.alice, 1), Ok(70));
+            assert_eq!(contract.get_past_votes(accounts.alice, 2), Ok(50));
+            assert_eq!(contract.get_past_votes(accounts.charlie, 0), Ok(0));
+            assert_eq!(contract.get_past_votes(accounts.charlie, 1), Ok(30));
+            assert_eq!(contract.get_past_votes(accounts.charlie, 2), Ok(50));
+            assert_eq!(
+                contract.get_past_votes(accounts.alice, 3),
+                Err(Error::FutureLookup)
+            );
+        }
+
+        #[ink::test]
+        fn past_total_supply_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.delegate(accounts.alice), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.mint(accounts.alice, 50), Ok(()));
+            assert_eq!(contract.get_votes(accounts.alice), 150);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.burn(30), Ok(()));
+            assert_eq!(contract.get_votes(accounts.alice), 120);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.get_past_total_supply(0), Ok(100));
+            assert_eq!(contract.get_past_total_supply(1), Ok(150));
+            assert_eq!(contract.get_past_total_supply(2), Ok(120));
+            assert_eq!(contract.get_past_votes(accounts.alice, 1), Ok(150));
+            assert_eq!(contract.get_past_total_supply(3), Err(Error::FutureLookup));
+        }
+
+        #[ink::test]
+        fn new_capped_works() {
+            let mut contract = Erc20::new_capped(100, 150);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.cap(), Some(150));
+            assert_eq!(Erc20::new(100).cap(), None);
+
+            assert_eq!(contract.mint(accounts.bob, 51), Err(Error::CapExceeded));
+            assert_eq!(contract.mint(accounts.bob, 50), Ok(()));
+            assert_eq!(contract.mint(accounts.bob, 1), Err(Error::CapExceeded));
+            assert_eq!(contract.total_supply(), 150);
+
+            // Burning frees up room below the cap.
+            assert_eq!(contract.burn(10), Ok(()));
+            assert_eq!(contract.mint(accounts.bob, 10), Ok(()));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "initial supply exceeds the cap")]
+        fn new_capped_fails_above_cap() {
+            let _ = Erc20::new_capped(101, 100);
+        }
+
+        #[ink::test]
+        fn flash_loan_limits_work() {
+            let contract = Erc20::new_capped(100, 1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let token = ink_env::account_id::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.max_flash_loan(token), 900);
+            assert_eq!(contract.max_flash_loan(accounts.bob), 0);
+            assert_eq!(contract.flash_fee(token, 500), Ok(0));
+            assert_eq!(
+                contract.flash_fee(accounts.bob, 500),
+                Err(Error::UnsupportedToken)
+            );
+            assert_eq!(Erc20::new(100).max_flash_loan(token), Balance::MAX - 100);
+        }
+
+        #[ink::test]
+        fn flash_loan_works() {
+            let mut contract = Erc20::new_capped(100, 1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let token = ink_env::account_id::<ink_env::DefaultEnvironment>();
+
+            // Bob's borrower allows the token to take the loan back.
+            set_borrower(move |contract, receiver, _, token, amount, fee| {
+                assert_eq!(contract.balance_of(receiver), amount);
+                ink_env::test::set_caller::<ink_env::DefaultEnvironment>(receiver);
+                assert_eq!(contract.approve(token, amount + fee + 100), Ok(()));
+                Ok(Ok(()))
+            });
+
+            assert_eq!(
+                contract.flash_loan(accounts.bob, token, 500, Vec::new()),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.allowance(accounts.bob, token), 100);
+            assert_eq!(contract.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn flash_loan_fails() {
+            let mut contract = Erc20::new_capped(100, 1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let token = ink_env::account_id::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                contract.flash_loan(accounts.bob, accounts.bob, 500, Vec::new()),
+                Err(Error::UnsupportedToken)
+            );
+            assert_eq!(
+                contract.flash_loan(accounts.bob, token, 901, Vec::new()),
+                Err(Error::ExceededMaxFlashLoan)
+            );
+            // Bob is not a borrower contract.
+            assert_eq!(
+                contract.flash_loan(accounts.bob, token, 100, Vec::new()),
+                Err(Error::FlashLoanRejected(String::from("NotCallable")))
+            );
+
+            set_borrower(|_, _, _, _, _, _| {
+                Ok(Err(FlashBorrowerError::FlashloanRejected(String::from(
+                    "no",
+                ))))
+            });
+            assert_eq!(
+                contract.flash_loan(accounts.bob, token, 100, Vec::new()),
+                Err(Error::FlashLoanRejected(String::from("no")))
+            );
+            set_borrower(|_, _, _, _, _, _| Err(ink_env::Error::CalleeTrapped));
+            assert_eq!(
+                contract.flash_loan(accounts.bob, token, 100, Vec::new()),
+                Err(Error::FlashLoanRejected(String::from("CalleeTrapped")))
+            );
+
+            // Bob's borrower never approves the repayment.
+            set_borrower(|_, _, _, _, _, _| Ok(Ok(())));
+            assert_eq!(
+                contract.flash_loan(accounts.bob, token, 100, Vec::new()),
+                Err(Error::FlashLoanRepaymentFailed)
+            );
+        }
+
+        #[ink::test]
+        fn native_deposit_and_withdraw_keep_supply_backed() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let wrapper = AccountId::from([0x42; 32]);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(wrapper);
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(wrapper, 0);
+            let mut contract = Erc20::new_native_wrapper();
+            assert!(contract.is_native_wrapper());
+            let assert_backed = |contract: &Erc20| {
+                assert_eq!(
+                    ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(wrapper),
+                    Ok(contract.total_supply())
+                );
+            };
+
+            ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(100);
+            assert_eq!(contract.deposit(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 100);
+            assert_backed(&contract);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(50);
+            assert_eq!(contract.deposit(), Ok(()));
+            assert_backed(&contract);
+
+            let bob_balance =
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.withdraw(51), Err(Error::InsufficientBalance));
+            assert_eq!(contract.withdraw(30), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 20);
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob),
+                bob_balance.map(|balance| balance + 30)
+            );
+            assert_backed(&contract);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.burn(10), Err(Error::BurningDisabled));
+            assert_eq!(contract.approve(accounts.bob, 10), Ok(()));
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.burn_from(accounts.alice, 10),
+                Err(Error::BurningDisabled)
+            );
+            assert_backed(&contract);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.withdraw(100), Ok(()));
+            assert_backed(&contract);
+            assert_eq!(contract.total_supply(), 20);
+        }
+
+        #[ink::test]
+        fn native_wrapper_cannot_mint_unbacked_tokens() {
+            let mut contract = Erc20::new_native_wrapper();
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let token = ink_env::account_id::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.total_supply(), 0);
+            assert_eq!(
+                contract.mint(accounts.alice, 10),
+                Err(Error::MintingDisabled)
+            );
+            assert_eq!(contract.max_flash_loan(token), 0);
+            assert_eq!(
+                contract.deposit_for(accounts.alice, 10),
+                Err(Error::NotWrapper)
+            );
+            assert_eq!(
+                contract.withdraw_to(accounts.alice, 10),
+                Err(Error::NotWrapper)
+            );
+            assert_eq!(contract.recover(), Err(Error::NotWrapper));
+        }
+
+        #[ink::test]
+        fn native_messages_fail_without_native_wrapper() {
+            let mut contract = Erc20::new(100);
+            assert!(!contract.is_native_wrapper());
+            ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(10);
+            assert_eq!(contract.deposit(), Err(Error::NotNativeWrapper));
+            // Tokens from the initial supply cannot claim native currency.
+            assert_eq!(contract.withdraw(10), Err(Error::NotNativeWrapper));
+            assert_eq!(contract.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn new_wrapper_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Erc20::new_wrapper(accounts.django);
+            assert_eq!(contract.underlying(), Some(accounts.django));
+            assert_eq!(contract.total_supply(), 0);
+            assert!(!contract.is_native_wrapper());
+
+            // Wrapper tokens are burned before any underlying tokens are sent.
+            assert_eq!(
+                contract.withdraw_to(accounts.alice, 1),
+                Err(Error::InsufficientBalance)
+            );
+        }
+
+        #[ink::test]
+        fn token_deposit_and_withdraw_keep_supply_backed() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let wrapper = AccountId::from([0x42; 32]);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(wrapper);
+            let mut contract = Erc20::new_wrapper(accounts.django);
+            let assert_backed = |contract: &Erc20| {
+                assert_eq!(underlying_balance_of(wrapper), contract.total_supply());
+            };
+            set_underlying_balance(accounts.alice, 100);
+
+            assert_eq!(
+                contract.deposit_for(accounts.bob, 101),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(contract.deposit_for(accounts.bob, 60), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 60);
+            assert_eq!(underlying_balance_of(accounts.alice), 40);
+            assert_backed(&contract);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.burn(10), Err(Error::BurningDisabled));
+            assert_eq!(contract.approve(accounts.alice, 10), Ok(()));
+            assert_eq!(contract.withdraw_to(accounts.charlie, 20), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 40);
+            assert_eq!(underlying_balance_of(accounts.charlie), 20);
+            assert_backed(&contract);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                contract.burn_from(accounts.bob, 10),
+                Err(Error::BurningDisabled)
+            );
+            assert_backed(&contract);
+        }
+
+        #[ink::test]
+        fn recover_mints_underlying_surplus() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let wrapper = AccountId::from([0x42; 32]);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(wrapper);
+            let mut contract = Erc20::new_wrapper(accounts.django);
+            set_underlying_balance(accounts.alice, 100);
+            assert_eq!(contract.deposit_for(accounts.alice, 50), Ok(()));
+            assert_eq!(contract.recover(), Ok(0));
+
+            // Sent to the wrapper directly instead of through `deposit_for`.
+            assert_eq!(underlying_transfer(accounts.alice, wrapper, 30), Ok(()));
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.recover(), Err(Error::MissingRole));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.recover(), Ok(30));
+            assert_eq!(contract.balance_of(accounts.alice), 80);
+            assert_eq!(underlying_balance_of(wrapper), contract.total_supply());
+        }
+
+        #[ink::test]
+        fn token_wrapper_cannot_mint_unbacked_tokens() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let token = ink_env::account_id::<ink_env::DefaultEnvironment>();
+            let mut contract = Erc20::new_wrapper(accounts.django);
+            assert_eq!(
+                contract.mint(accounts.alice, 10),
+                Err(Error::MintingDisabled)
+            );
+            assert_eq!(contract.max_flash_loan(token), 0);
+            ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(10);
+            assert_eq!(contract.deposit(), Err(Error::NotNativeWrapper));
+            assert_eq!(contract.withdraw(0), Err(Error::NotNativeWrapper));
+            assert_eq!(contract.total_supply(), 0);
+        }
+
+        #[ink::test]
+        fn wrapper_messages_fail_without_underlying() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.underlying(), None);
+            assert_eq!(
+                contract.deposit_for(accounts.bob, 10),
+                Err(Error::NotWrapper)
+            );
+            assert_eq!(
+                contract.withdraw_to(accounts.bob, 10),
+                Err(Error::NotWrapper)
+            );
+            assert_eq!(contract.recover(), Err(Error::NotWrapper));
+            assert_eq!(contract.balance_of(accounts.alice), 100);
+        }
+
+        #[ink::test]
+        fn vesting_releases_linearly() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let token = AccountId::from([0x42; 32]);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(token);
+            let mut contract = Erc20::new(1_000);
+
+            assert_eq!(
+                contract.create_vesting(accounts.bob, 600, 0, 12, 60, false),
+                Ok(0)
+            );
+            assert_eq!(contract.balance_of(accounts.alice), 400);
+            assert_eq!(contract.balance_of(token), 600);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            // Still before the cliff.
+            assert_eq!(contract.releasable(accounts.bob), 0);
+            let emitted_events = ink_env::test::recorded_events().count();
+            assert_eq!(contract.release(), Ok(0));
+            assert_eq!(ink_env::test::recorded_events().count(), emitted_events);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.releasable(accounts.bob), 120);
+            assert_eq!(contract.release(), Ok(120));
+            assert_eq!(contract.balance_of(accounts.bob), 120);
+            assert_eq!(contract.releasable(accounts.bob), 0);
+
+            for _ in 0..10 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(contract.release(), Ok(480));
+            assert_eq!(contract.balance_of(accounts.bob), 600);
+            assert_eq!(contract.balance_of(token), 0);
+            assert_eq!(
+                contract
+                    .vesting_schedule(accounts.bob, 0)
+                    .map(|s| s.released),
+                Some(600)
+            );
+        }
+
+        #[ink::test]
+        fn vesting_supports_multiple_schedules() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            let mut contract = Erc20::new(1_000);
+
+            assert_eq!(
+                contract.create_vesting(accounts.bob, 60, 0, 0, 60, false),
+                Ok(0)
+            );
+            assert_eq!(
+                contract.create_vesting(accounts.bob, 100, 30, 0, 10, false),
+                Ok(1)
+            );
+            assert_eq!(contract.vesting_schedule_count(accounts.bob), 2);
+            assert_eq!(
+                contract.create_vesting(accounts.bob, 100, 0, 20, 10, false),
+                Err(Error::InvalidVestingSchedule)
+            );
+
+            for _ in 0..6 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            // 36 of the first schedule and 60 of the second have vested.
+            assert_eq!(contract.releasable(accounts.bob), 96);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.create_vesting(accounts.bob, 1, 0, 0, 1, false),
+                Err(Error::MissingRole)
+            );
+            assert_eq!(contract.release(), Ok(96));
+            assert_eq!(contract.balance_of(accounts.bob), 96);
+        }
+
+        #[ink::test]
+        fn revoke_vesting_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            let mut contract = Erc20::new(1_000);
+            assert_eq!(
+                contract.create_vesting(accounts.bob, 600, 0, 0, 60, true),
+                Ok(0)
+            );
+            assert_eq!(
+                contract.create_vesting(accounts.bob, 300, 0, 0, 60, false),
+                Ok(1)
+            );
+            assert_eq!(
+                contract.revoke_vesting(accounts.bob, 1),
+                Err(Error::VestingNotRevocable)
+            );
+            assert_eq!(
+                contract.revoke_vesting(accounts.bob, 2),
+                Err(Error::VestingScheduleNotFound)
+            );
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.revoke_vesting(accounts.bob, 0), Ok(540));
+            assert_eq!(contract.balance_of(accounts.alice), 640);
+            assert_eq!(
+                contract.revoke_vesting(accounts.bob, 0),
+                Err(Error::VestingNotRevocable)
+            );
+
+            // The tokens vested before revocation stay releasable.
+            for _ in 0..10 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.release(), Ok(360));
         }
 
         #[ink::test]
-        fn balance_works() {
-            let contract = Erc20::new(100);
-            assert_eq!(contract.total_supply(), 100);
-            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 0);
+        fn transfer_locked_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                contract.transfer_locked(accounts.bob, 30, 0),
+                Err(Error::InvalidUnlockTime)
+            );
+            assert_eq!(contract.transfer_locked(accounts.bob, 30, 12), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 60);
+            assert_eq!(contract.balance_of(accounts.bob), 40);
+            assert_eq!(contract.locked_balance_of(accounts.bob), 30);
+            assert_eq!(contract.spendable_balance_of(accounts.bob), 10);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer(accounts.alice, 11),
+                Err(Error::TokensLocked)
+            );
+            assert_eq!(contract.burn(11), Err(Error::TokensLocked));
+            assert_eq!(contract.transfer(accounts.alice, 10), Ok(()));
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.locked_balance_of(accounts.bob), 30);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.locked_balance_of(accounts.bob), 0);
+            assert_eq!(contract.spendable_balance_of(accounts.bob), 30);
+            assert_eq!(contract.transfer(accounts.alice, 30), Ok(()));
         }
 
         #[ink::test]
-        fn transfer_works() {
-            let mut erc20 = Erc20::new(100);
-            assert_eq!(erc20.balance_of(AccountId::from([0x0; 32])), 0);
-            assert_eq!(erc20.transfer(AccountId::from([0x0; 32]), 10), Ok(()));
-            assert_eq!(erc20.balance_of(AccountId::from([0x0; 32])), 10);
+        fn transfer_locked_limits_locks() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                contract.transfer_locked(accounts.bob, 0, 12),
+                Err(Error::ZeroLockValue)
+            );
+            for _ in 0..MAX_LOCKS_PER_ACCOUNT {
+                assert_eq!(contract.transfer_locked(accounts.bob, 1, 12), Ok(()));
+            }
+            assert_eq!(
+                contract.transfer_locked(accounts.bob, 1, 12),
+                Err(Error::TooManyLocks)
+            );
+            assert_eq!(contract.transfer(accounts.bob, 1), Ok(()));
+
+            // Only lockers can take up lock slots.
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_locked(accounts.charlie, 1, Timestamp::MAX),
+                Err(Error::MissingRole)
+            );
+            assert_eq!(contract.locked_balance_of(accounts.charlie), 0);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+
+            // Expired locks free their slots.
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer_locked(accounts.bob, 1, 18), Ok(()));
+            assert_eq!(contract.lock_counts.get(accounts.bob), Some(1));
+        }
+
+        #[ink::test]
+        fn transfer_locked_drops_expired_locks() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.transfer_locked(accounts.bob, 10, 6), Ok(()));
+            assert_eq!(contract.transfer_locked(accounts.bob, 20, 18), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer_locked(accounts.bob, 30, 24), Ok(()));
+
+            assert_eq!(contract.lock_counts.get(accounts.bob), Some(2));
+            assert_eq!(contract.locked_balance_of(accounts.bob), 50);
+            assert_eq!(contract.spendable_balance_of(accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn transfer_fee_goes_to_treasury() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            let mut contract = Erc20::new(10_000);
+            assert_eq!(contract.set_transfer_fee(250, Some(accounts.eve)), Ok(()));
+            assert_eq!(contract.transfer_fee_bps(), 250);
+            assert_eq!(contract.treasury(), Some(accounts.eve));
+            assert_eq!(
+                contract.grant_role(DEFAULT_ADMIN_ROLE, accounts.frank),
+                Ok(())
+            );
+            assert_eq!(contract.approve(accounts.frank, 2_000), Ok(()));
+            let events_before = ink_env::test::recorded_events().count();
+
+            assert_eq!(contract.transfer(accounts.bob, 1_000), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 9_000);
+            assert_eq!(contract.balance_of(accounts.bob), 975);
+            assert_eq!(contract.balance_of(accounts.eve), 25);
+            // One `Transfer` event per leg.
+            assert_eq!(ink_env::test::recorded_events().count(), events_before + 2);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.charlie, 2_000),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.charlie), 1_950);
+            assert_eq!(contract.balance_of(accounts.eve), 75);
+            assert_eq!(contract.total_supply(), 10_000);
+        }
+
+        #[ink::test]
+        fn transfer_fee_rounds_down() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            let mut contract = Erc20::new(10_000);
+            assert_eq!(contract.set_transfer_fee(250, Some(accounts.eve)), Ok(()));
+
+            // 39 * 2.5% = 0.975 is rounded down to no fee.
+            assert_eq!(contract.transfer(accounts.bob, 39), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 39);
+            assert_eq!(contract.balance_of(accounts.eve), 0);
+
+            // 79 * 2.5% = 1.975 is rounded down to 1.
+            assert_eq!(contract.transfer(accounts.bob, 79), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 117);
+            assert_eq!(contract.balance_of(accounts.eve), 1);
+        }
+
+        #[ink::test]
+        fn transfer_fee_exemptions_work() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            let mut contract = Erc20::new(10_000);
+            assert_eq!(contract.set_transfer_fee(1_000, Some(accounts.eve)), Ok(()));
+            assert_eq!(contract.set_fee_exempt(accounts.bob, true), Ok(()));
+            assert!(contract.is_fee_exempt(accounts.bob));
+
+            assert_eq!(contract.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+            assert_eq!(contract.transfer(accounts.eve, 100), Ok(()));
+            assert_eq!(contract.balance_of(accounts.eve), 100);
+
+            assert_eq!(contract.set_fee_exempt(accounts.bob, false), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 190);
+            assert_eq!(contract.balance_of(accounts.eve), 110);
+        }
+
+        #[ink::test]
+        fn set_transfer_fee_is_capped_and_restricted() {
+            let mut contract = Erc20::new(10_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                contract.set_transfer_fee(MAX_TRANSFER_FEE_BPS + 1, Some(accounts.eve)),
+                Err(Error::FeeTooHigh)
+            );
+            assert_eq!(
+                contract.set_transfer_fee(MAX_TRANSFER_FEE_BPS, Some(accounts.eve)),
+                Ok(())
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.set_transfer_fee(0, None), Err(Error::MissingRole));
+            assert_eq!(
+                contract.set_fee_exempt(accounts.bob, true),
+                Err(Error::MissingRole)
+            );
+        }
+
+        #[ink::test]
+        fn frozen_accounts_cannot_move_tokens() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(accounts.bob, 50), Ok(()));
+
+            assert_eq!(contract.freeze(accounts.bob), Ok(()));
+            assert!(contract.is_frozen(accounts.bob));
+            assert_eq!(
+                contract.transfer(accounts.bob, 10),
+                Err(Error::AccountFrozen)
+            );
+            assert_eq!(
+                contract.approve(accounts.bob, 10),
+                Err(Error::AccountFrozen)
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer(accounts.alice, 10),
+                Err(Error::AccountFrozen)
+            );
+            assert_eq!(
+                contract.approve(accounts.charlie, 10),
+                Err(Error::AccountFrozen)
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 50);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.unfreeze(accounts.bob), Ok(()));
+            assert!(!contract.is_frozen(accounts.bob));
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn frozen_accounts_cannot_mint_burn_or_unwrap() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            let mut contract = Erc20::new_native_wrapper();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(50);
+            assert_eq!(contract.deposit(), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.freeze(accounts.bob), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.withdraw(10), Err(Error::AccountFrozen));
+            ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(10);
+            assert_eq!(contract.deposit(), Err(Error::AccountFrozen));
+            assert_eq!(contract.balance_of(accounts.bob), 50);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(AccountId::from([0x43; 32]));
+            let mut token = Erc20::new(100);
+            assert_eq!(token.transfer(accounts.bob, 50), Ok(()));
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.approve(accounts.alice, 50), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(token.freeze(accounts.bob), Ok(()));
+            assert_eq!(token.burn_from(accounts.bob, 10), Err(Error::AccountFrozen));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.burn(10), Err(Error::AccountFrozen));
+            assert_eq!(token.balance_of(accounts.bob), 50);
+        }
+
+        #[ink::test]
+        fn frozen_spender_cannot_transfer_from() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.freeze(accounts.bob), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.charlie, 10),
+                Err(Error::AccountFrozen)
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 10);
+            assert_eq!(contract.freeze(accounts.alice), Err(Error::MissingRole));
+        }
+
+        #[ink::test]
+        fn batch_transfer_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let events_before = ink_env::test::recorded_events().count();
+            assert_eq!(
+                contract.batch_transfer(vec![(accounts.bob, 10), (accounts.charlie, 20)]),
+                Ok(())
+            );
+            assert_eq!(ink_env::test::recorded_events().count(), events_before + 2);
+            assert_eq!(contract.balance_of(accounts.alice), 70);
+            assert_eq!(contract.balance_of(accounts.bob), 10);
+            assert_eq!(contract.balance_of(accounts.charlie), 20);
+        }
+
+        #[ink::test]
+        fn batch_transfer_fails_for_total_or_entry() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                contract.batch_transfer(vec![(accounts.bob, 60), (accounts.charlie, 50)]),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(
+                contract.batch_transfer(vec![(accounts.bob, Balance::MAX), (accounts.bob, 1)]),
+                Err(Error::Overflow)
+            );
+            assert_eq!(contract.balance_of(accounts.alice), 100);
+
+            assert_eq!(contract.freeze(accounts.charlie), Ok(()));
+            assert_eq!(
+                contract.batch_transfer(vec![(accounts.bob, 10), (accounts.charlie, 20)]),
+                Err(Error::BatchTransferFailed(
+                    1,
+                    Box::new(Error::AccountFrozen)
+                ))
+            );
+            // Recipients are checked before any balance changes.
+            assert_eq!(contract.balance_of(accounts.alice), 100);
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn batch_transfer_debits_sender_once() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            let mut contract = Erc20::new(10_000);
+            assert_eq!(contract.delegate(accounts.alice), Ok(()));
+            assert_eq!(contract.set_transfer_fee(250, Some(accounts.eve)), Ok(()));
+            let events_before = ink_env::test::recorded_events().count();
+
+            assert_eq!(
+                contract.batch_transfer(vec![(accounts.bob, 1_000), (accounts.charlie, 2_000)]),
+                Ok(())
+            );
+            // One `DelegateVotesChanged` for the sender and two `Transfer` legs per
+            // entry.
+            assert_eq!(ink_env::test::recorded_events().count(), events_before + 5);
+            assert_eq!(contract.get_votes(accounts.alice), 7_000);
+            assert_eq!(contract.balance_of(accounts.bob), 975);
+            assert_eq!(contract.balance_of(accounts.charlie), 1_950);
+            assert_eq!(contract.balance_of(accounts.eve), 75);
+        }
+
+        #[ink::test]
+        fn batch_transfer_from_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.approve(accounts.bob, 30), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.batch_transfer_from(
+                    accounts.alice,
+                    vec![(accounts.charlie, 20), (accounts.django, 20)]
+                ),
+                Err(Error::InsufficientAllowance)
+            );
+            assert_eq!(
+                contract.batch_transfer_from(
+                    accounts.alice,
+                    vec![(accounts.charlie, 10), (accounts.django, 20)]
+                ),
+                Ok(())
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(contract.balance_of(accounts.alice), 70);
+            assert_eq!(contract.balance_of(accounts.charlie), 10);
+            assert_eq!(contract.balance_of(accounts.django), 20);
+        }
+
+        #[ink::test]
+        fn controller_transfer_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(contract.grant_role(CONTROLLER_ROLE, accounts.alice), Ok(()));
+            assert_eq!(contract.freeze(accounts.bob), Ok(()));
+
+            let events_before = ink_env::test::recorded_events().count();
+            assert_eq!(
+                contract.controller_transfer(
+                    accounts.bob,
+                    accounts.charlie,
+                    30,
+                    b"court order".to_vec()
+                ),
+                Ok(())
+            );
+            // `Transfer` and `ControllerTransfer`.
+            assert_eq!(ink_env::test::recorded_events().count(), events_before + 2);
+            assert_eq!(contract.balance_of(accounts.bob), 20);
+            assert_eq!(contract.balance_of(accounts.charlie), 30);
+            assert_eq!(contract.allowance(accounts.bob, accounts.alice), 0);
+
+            assert_eq!(
+                contract.controller_transfer(accounts.bob, accounts.charlie, 21, Vec::new()),
+                Err(Error::InsufficientBalance)
+            );
+
+            // Tokens cannot be moved to a frozen account.
+            assert_eq!(
+                contract.controller_transfer(accounts.charlie, accounts.bob, 10, Vec::new()),
+                Err(Error::AccountFrozen)
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 20);
+        }
+
+        #[ink::test]
+        fn controller_transfer_releases_moved_locks() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.grant_role(CONTROLLER_ROLE, accounts.alice), Ok(()));
+            assert_eq!(contract.transfer_locked(accounts.bob, 20, 12), Ok(()));
+            assert_eq!(contract.transfer_locked(accounts.bob, 10, 18), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+
+            // 10 unlocked tokens, then 20 out of the oldest lock.
+            assert_eq!(
+                contract.controller_transfer(accounts.bob, accounts.charlie, 30, Vec::new()),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 10);
+            assert_eq!(contract.locked_balance_of(accounts.bob), 10);
+            assert_eq!(contract.active_lock_count(&accounts.bob), 1);
+
+            // Tokens received afterwards are spendable.
+            assert_eq!(contract.transfer(accounts.bob, 5), Ok(()));
+            assert_eq!(contract.spendable_balance_of(accounts.bob), 5);
+        }
+
+        #[ink::test]
+        fn controller_transfer_requires_role() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                contract.controller_transfer(accounts.alice, accounts.bob, 10, Vec::new()),
+                Err(Error::MissingRole)
+            );
+            assert_eq!(contract.balance_of(accounts.alice), 100);
+        }
+
+        #[ink::test]
+        fn allowlist_restricts_holders() {
+            let mut contract = Erc20::new_with_allowlist(100, true);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert!(contract.allowlist_enabled());
+            assert!(contract.is_allowlisted(accounts.alice));
+            assert_eq!(contract.balance_of(accounts.alice), 100);
+            // Initial transfer, ownership, five seeded roles, the caller and the
+            // contract being allowlisted.
+            assert_eq!(ink_env::test::recorded_events().count(), 9);
+
+            assert_eq!(
+                contract.transfer(accounts.bob, 10),
+                Err(Error::NotAllowlisted)
+            );
+            assert_eq!(contract.mint(accounts.bob, 10), Err(Error::NotAllowlisted));
+            assert_eq!(contract.allowlist_add(accounts.bob), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), 10);
+            assert!(contract.is_allowlisted(accounts.bob));
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.mint(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 20);
+
+            let emitted_events = ink_env::test::recorded_events().count();
+            assert_eq!(contract.allowlist_remove(accounts.bob), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), emitted_events + 1);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.burn(10), Err(Error::NotAllowlisted));
+            // Tokens can still be returned to an allowlisted holder.
+            assert_eq!(contract.transfer(accounts.alice, 20), Ok(()));
+            assert_eq!(
+                contract.allowlist_add(accounts.bob),
+                Err(Error::MissingRole)
+            );
+            assert_eq!(contract.disable_allowlist(), Err(Error::MissingRole));
+            assert!(contract.allowlist_enabled());
+        }
+
+        #[ink::test]
+        fn allowlist_mode_is_opt_in() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Erc20::new_with_allowlist(100, false);
+            assert!(!contract.allowlist_enabled());
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            let mut contract = Erc20::new_with_allowlist(100, true);
+            assert_eq!(
+                contract.transfer(accounts.bob, 10),
+                Err(Error::NotAllowlisted)
+            );
+            let emitted_events = ink_env::test::recorded_events().count();
+            assert_eq!(contract.disable_allowlist(), Ok(()));
+            assert!(!contract.allowlist_enabled());
+            assert_eq!(contract.disable_allowlist(), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), emitted_events + 1);
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn vesting_works_in_allowlist_mode() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let token = AccountId::from([0x42; 32]);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(token);
+            let mut contract = Erc20::new_with_allowlist(1_000, true);
+            assert!(contract.is_allowlisted(token));
+            assert_eq!(contract.allowlist_add(accounts.bob), Ok(()));
+
+            assert_eq!(
+                contract.create_vesting(accounts.bob, 600, 0, 0, 6, false),
+                Ok(0)
+            );
+            assert_eq!(contract.balance_of(token), 600);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.release(), Ok(600));
+            assert_eq!(contract.balance_of(accounts.bob), 600);
+        }
+
+        #[ink::test]
+        fn transfer_fee_requires_eligible_treasury() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            let mut contract = Erc20::new_with_allowlist(10_000, true);
+            assert_eq!(contract.set_transfer_fee(250, Some(accounts.eve)), Ok(()));
+            assert_eq!(contract.allowlist_add(accounts.bob), Ok(()));
+
+            assert_eq!(
+                contract.transfer(accounts.bob, 1_000),
+                Err(Error::NotAllowlisted)
+            );
+            assert_eq!(contract.allowlist_add(accounts.eve), Ok(()));
+            assert_eq!(contract.freeze(accounts.eve), Ok(()));
+            assert_eq!(
+                contract.transfer(accounts.bob, 1_000),
+                Err(Error::AccountFrozen)
+            );
+            assert_eq!(contract.balance_of(accounts.eve), 0);
+
+            assert_eq!(contract.unfreeze(accounts.eve), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 1_000), Ok(()));
+            assert_eq!(contract.balance_of(accounts.eve), 25);
+        }
+
+        #[ink::test]
+        fn burn_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.burn(30), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 70);
+            assert_eq!(contract.total_supply(), 70);
+
+            assert_eq!(contract.burn(71), Err(Error::InsufficientBalance));
+            assert_eq!(contract.total_supply(), 70);
+        }
+
+        #[ink::test]
+        fn burn_from_works() {
+            let mut contract = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.approve(accounts.bob, 40), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.burn_from(accounts.alice, 50),
+                Err(Error::InsufficientAllowance)
+            );
+            assert_eq!(contract.burn_from(accounts.alice, 30), Ok(()));
+
+            assert_eq!(contract.balance_of(accounts.alice), 70);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 10);
+            assert_eq!(contract.total_supply(), 70);
         }
 
         #[ink::test]